@@ -0,0 +1,230 @@
+//! Run the requests of a workspace without the GTK interface.
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+use regex::Regex;
+use tokio::runtime::Runtime;
+
+use crate::errors::{RustamanError, RustamanResult};
+use crate::helpers::report::{self, TestCase};
+use crate::helpers::runner::{self, RunEvent};
+use crate::helpers::{httpparser, path};
+use crate::models::{Environment, Request, Workspace};
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum ReportFormat {
+    Junit,
+    Tap,
+}
+
+/// The environment named, or the first active one.
+fn find_environment<'a>(
+    workspace: &'a Workspace,
+    name: Option<&str>,
+) -> RustamanResult<&'a Environment> {
+    let environ = match name {
+        Some(name) => workspace.environment_by_name(name),
+        None => workspace.environments().iter().find(|env| env.active()),
+    };
+    environ.ok_or_else(|| {
+        RustamanError::NotFound(format!("Environment {} not found", name.unwrap_or("")))
+    })
+}
+
+fn build_runtime() -> RustamanResult<Runtime> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    Ok(runtime)
+}
+
+pub fn run_request(
+    workspace: &Workspace,
+    env: Option<&str>,
+    name: &str,
+    include: bool,
+    verbose: bool,
+) -> RustamanResult<()> {
+    let request = workspace
+        .request_by_name(name)
+        .ok_or_else(|| RustamanError::NotFound(format!("Request {} not found", name)))?;
+    let environ = find_environment(workspace, env)?.clone();
+    let runtime = build_runtime()?;
+    let mut stdout = io::stdout().lock();
+    let mut failures = 0;
+    let template = request.template().to_string();
+    runtime.block_on(runner::run(template, environ, |event| match event {
+        RunEvent::Sending(obfuscated_request, _) => {
+            if verbose {
+                for line in obfuscated_request.http_frame().lines() {
+                    eprintln!("> {}", line);
+                }
+            }
+        }
+        RunEvent::Received(response) => {
+            // the body is written as received, so a binary body can be piped.
+            let output = if include {
+                response.http_frame().into_bytes()
+            } else {
+                response.raw_body().to_vec()
+            };
+            let _ = stdout.write_all(output.as_slice());
+            if !response.is_binary() && !output.ends_with(b"\n") {
+                let _ = stdout.write_all(b"\n");
+            }
+        }
+        RunEvent::SendingError(error) => {
+            debug!("Request failed: {}", error);
+        }
+        RunEvent::Asserted(assertions) => {
+            for assertion in assertions.iter() {
+                match assertion.failure() {
+                    None => eprintln!("PASS {}", assertion.expect()),
+                    Some(failure) => {
+                        failures += 1;
+                        eprintln!("FAIL {}: {}", assertion.expect(), failure);
+                    }
+                }
+            }
+        }
+    }))?;
+    if failures > 0 {
+        return Err(RustamanError::AssertionError(format!(
+            "{} expectation(s) failed",
+            failures
+        )));
+    }
+    Ok(())
+}
+
+/// Run every blocks of the request, each block is a test case.
+fn run_test_cases(
+    runtime: &Runtime,
+    request: &Request,
+    environ: &Environment,
+    cases: &mut Vec<TestCase>,
+) {
+    let templates = httpparser::split_template(request.template());
+    let first = cases.len();
+    let case_name = |idx: usize| {
+        if templates.len() > 1 {
+            format!("{} #{}", request.name(), idx + 1)
+        } else {
+            request.name().to_string()
+        }
+    };
+    let mut recorded = false;
+    let result = runtime.block_on(runner::run(
+        request.template().to_string(),
+        environ.clone(),
+        |event| match event {
+            RunEvent::Sending(_, _) => {
+                let name = case_name(cases.len() - first);
+                cases.push(TestCase::new(request.name(), name.as_str()));
+            }
+            RunEvent::Received(response) => {
+                if let Some(case) = cases.last_mut() {
+                    case.status = Some(response.status());
+                    case.duration = response.elapsed();
+                }
+            }
+            RunEvent::Asserted(assertions) => {
+                if let Some(case) = cases.last_mut() {
+                    for assertion in assertions.iter() {
+                        if let Some(failure) = assertion.failure() {
+                            case.failures
+                                .push(format!("{}: {}", assertion.expect(), failure));
+                        }
+                    }
+                }
+            }
+            RunEvent::SendingError(error) => {
+                recorded = true;
+                if let Some(case) = cases.last_mut() {
+                    case.error = Some(error);
+                }
+            }
+        },
+    ));
+
+    if let Err(err) = result {
+        if !recorded {
+            match &err {
+                // captures are extracted once the response has been received
+                RustamanError::CaptureError(_) | RustamanError::JsonPathError(_)
+                    if cases.len() > first =>
+                {
+                    cases.last_mut().unwrap().error = Some(err.to_string());
+                }
+                _ => {
+                    let name = case_name(cases.len() - first);
+                    let mut case = TestCase::new(request.name(), name.as_str());
+                    case.error = Some(err.to_string());
+                    cases.push(case);
+                }
+            }
+        }
+    }
+    while cases.len() - first < templates.len() {
+        let name = case_name(cases.len() - first);
+        let mut case = TestCase::new(request.name(), name.as_str());
+        case.skipped = true;
+        cases.push(case);
+    }
+}
+
+/// Run the requests of the workspace, or the ones matching the filter,
+/// and write a report.
+pub fn run_collection(
+    workspace: &Workspace,
+    env: Option<&str>,
+    filter: Option<&str>,
+    format: ReportFormat,
+    output: Option<&str>,
+) -> RustamanResult<()> {
+    let filter = match filter {
+        Some(filter) => Some(Regex::new(filter).map_err(|err| {
+            RustamanError::InvalidArgument(format!("Invalid filter {}: {}", filter, err))
+        })?),
+        None => None,
+    };
+    let environ = find_environment(workspace, env)?;
+    let runtime = build_runtime()?;
+
+    let mut cases = Vec::new();
+    for request in workspace.requests().iter() {
+        if !request.active() {
+            continue;
+        }
+        if let Some(filter) = &filter {
+            if !filter.is_match(request.name()) {
+                continue;
+            }
+        }
+        info!("Running request {}", request.name());
+        run_test_cases(&runtime, request, environ, &mut cases);
+    }
+
+    let report = match format {
+        ReportFormat::Junit => report::junit(workspace.name(), cases.as_slice()),
+        ReportFormat::Tap => report::tap(cases.as_slice()),
+    };
+    match output {
+        Some(filepath) => path::write_file(filepath, report.as_str())?,
+        None => io::stdout().write_all(report.as_bytes())?,
+    }
+
+    // the blocks are skipped after a failure, the run has already failed.
+    let failed = cases
+        .iter()
+        .filter(|case| !case.passed() && !case.skipped)
+        .count();
+    let skipped = cases.iter().filter(|case| case.skipped).count();
+    if failed > 0 {
+        return Err(RustamanError::AssertionError(format!(
+            "{} test(s) failed, {} skipped",
+            failed, skipped
+        )));
+    }
+    Ok(())
+}
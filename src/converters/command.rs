@@ -0,0 +1,229 @@
+//! Export a rendered request as a command line of another http client.
+use reqwest::{Method, Version};
+use serde_json::Value as Json;
+
+use super::template_body;
+use crate::errors::RustamanResult;
+use crate::helpers::capture;
+use crate::helpers::httpparser::{self, HttpRequest};
+use crate::models::Environment;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommandFormat {
+    Curl,
+    Httpie,
+    Wget,
+}
+
+/// Quote the value for a posix shell.
+pub fn shell_quote(value: &str) -> String {
+    if !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c))
+    {
+        return value.to_string();
+    }
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn curl(httpreq: &HttpRequest) -> String {
+    let mut args = vec!["curl".to_string()];
+    if !httpreq.verify_cert() {
+        args.push("-k".to_string());
+    }
+    if let Some((host, port)) = httpreq.authority() {
+        args.push("--connect-to".to_string());
+        args.push(shell_quote(format!("::{}:{}", host, port).as_str()));
+    }
+    match httpreq.version() {
+        Some(Version::HTTP_10) => args.push("--http1.0".to_string()),
+        Some(Version::HTTP_11) => args.push("--http1.1".to_string()),
+        Some(Version::HTTP_2) => args.push("--http2-prior-knowledge".to_string()),
+        _ => {}
+    }
+    // curl waits for a body after -X HEAD.
+    if httpreq.method() == Method::HEAD {
+        args.push("-I".to_string());
+    } else {
+        args.push("-X".to_string());
+        args.push(httpreq.method().to_string());
+    }
+    args.push(shell_quote(httpreq.url()));
+    for (key, val) in httpreq.headers() {
+        args.push("-H".to_string());
+        args.push(shell_quote(format!("{}: {}", key, val).as_str()));
+    }
+    if let Some(body) = template_body(httpreq) {
+        args.push("--data-raw".to_string());
+        args.push(shell_quote(body.as_str()));
+    }
+    args.join(" ")
+}
+
+fn httpie(httpreq: &HttpRequest) -> String {
+    let mut args = vec!["http".to_string()];
+    if !httpreq.verify_cert() {
+        args.push("--verify=no".to_string());
+    }
+    if let Some(body) = template_body(httpreq) {
+        args.push("--raw".to_string());
+        args.push(shell_quote(body.as_str()));
+    }
+    args.push(httpreq.method().to_string());
+    args.push(shell_quote(httpreq.url()));
+    for (key, val) in httpreq.headers() {
+        args.push(shell_quote(format!("{}:{}", key, val).as_str()));
+    }
+    args.join(" ")
+}
+
+fn wget(httpreq: &HttpRequest) -> String {
+    let mut args = vec!["wget".to_string(), "-O".to_string(), "-".to_string()];
+    if !httpreq.verify_cert() {
+        args.push("--no-check-certificate".to_string());
+    }
+    args.push(format!("--method={}", httpreq.method()));
+    for (key, val) in httpreq.headers() {
+        args.push(format!(
+            "--header={}",
+            shell_quote(format!("{}: {}", key, val).as_str())
+        ));
+    }
+    if let Some(body) = template_body(httpreq) {
+        args.push(format!("--body-data={}", shell_quote(body.as_str())));
+    }
+    args.push(shell_quote(httpreq.url()));
+    args.join(" ")
+}
+
+pub fn export(httpreq: &HttpRequest, format: CommandFormat) -> String {
+    match format {
+        CommandFormat::Curl => curl(httpreq),
+        CommandFormat::Httpie => httpie(httpreq),
+        CommandFormat::Wget => wget(httpreq),
+    }
+}
+
+/// Render every request of the template with the environment, then export them.
+///
+/// The values captured in the responses are unknown, their `{{name}}` is kept.
+pub fn export_template(
+    template: &str,
+    environ: &Environment,
+    format: CommandFormat,
+) -> RustamanResult<String> {
+    let mut context = environ.parsed_payload()?;
+    let mut placeholders: Vec<String> = Vec::new();
+    let mut commands = Vec::new();
+    for block in httpparser::split_template(template).iter() {
+        let httpreq = httpparser::load_template_with_context(block.as_str(), &context)?;
+        let mut command = export(&httpreq, format);
+        let captured: Vec<&str> = placeholders
+            .iter()
+            .filter(|placeholder| command.contains(placeholder.as_str()))
+            .map(|placeholder| placeholder.as_str())
+            .collect();
+        if !captured.is_empty() {
+            command = format!(
+                "# Replace {}, captured from the previous responses\n{}",
+                captured.join(", "),
+                command
+            );
+        }
+        commands.push(command);
+        for capture in httpreq.captures() {
+            let placeholder = format!("{{{{{}}}}}", capture.name());
+            capture::inject(
+                &mut context,
+                capture.name(),
+                Json::String(placeholder.clone()),
+            )?;
+            placeholders.push(placeholder);
+        }
+    }
+    Ok(commands.join("\n\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &str = "#! Authority: localhost:8080
+#! AllowInsecureCertificate
+POST http://example.com/users?q=a&page=1
+Content-Type: application/json
+X-Name: O'Brien
+
+{\"name\": \"O'Brien\"}
+";
+
+    fn command(template: &str, format: CommandFormat) -> String {
+        export(&httpparser::parse_template(template).unwrap(), format)
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("http://example.com/a"), "http://example.com/a");
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("O'Brien"), "'O'\\''Brien'");
+        assert_eq!(shell_quote("$HOME"), "'$HOME'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn test_curl() {
+        assert_eq!(
+            command(TEMPLATE, CommandFormat::Curl),
+            "curl -k --connect-to ::localhost:8080 -X POST 'http://example.com/users?q=a&page=1' \
+             -H 'Content-Type: application/json' -H 'X-Name: O'\\''Brien' \
+             --data-raw '{\"name\": \"O'\\''Brien\"}'"
+        );
+        assert_eq!(
+            command("HEAD http://example.com/\n", CommandFormat::Curl),
+            "curl -I http://example.com/"
+        );
+        assert_eq!(
+            command("GET http://example.com/ HTTP/1.0\n", CommandFormat::Curl),
+            "curl --http1.0 -X GET http://example.com/"
+        );
+    }
+
+    #[test]
+    fn test_httpie() {
+        assert_eq!(
+            command(TEMPLATE, CommandFormat::Httpie),
+            "http --verify=no --raw '{\"name\": \"O'\\''Brien\"}' POST \
+             'http://example.com/users?q=a&page=1' Content-Type:application/json \
+             'X-Name:O'\\''Brien'"
+        );
+    }
+
+    #[test]
+    fn test_wget() {
+        assert_eq!(
+            command(TEMPLATE, CommandFormat::Wget),
+            "wget -O - --no-check-certificate --method=POST \
+             --header='Content-Type: application/json' --header='X-Name: O'\\''Brien' \
+             --body-data='{\"name\": \"O'\\''Brien\"}' 'http://example.com/users?q=a&page=1'"
+        );
+    }
+
+    #[test]
+    fn test_export_template() {
+        let environ = Environment::new(1, "Dev", "url: http://example.com\n");
+        let template = "#! Capture: token = $.token
+POST {{url}}/login
+
+#! EndCapture
+GET {{url}}/me
+Authorization: Bearer {{token}}
+";
+        assert_eq!(
+            export_template(template, &environ, CommandFormat::Curl).unwrap(),
+            "curl -X POST http://example.com/login\n\n\
+             # Replace {{token}}, captured from the previous responses\n\
+             curl -X GET http://example.com/me -H 'Authorization: Bearer {{token}}'"
+        );
+    }
+}
@@ -0,0 +1,422 @@
+//! Import the curl command lines, like the "Copy as cURL" of the browsers.
+use std::iter::Peekable;
+use std::str::Chars;
+
+use url::form_urlencoded;
+
+use super::{
+    build_literal_template, multipart_body, request_name, ImportedRequest, MULTIPART_BOUNDARY,
+};
+use crate::errors::{RustamanError, RustamanResult};
+use crate::helpers::base64;
+
+/// Short options of curl that consume a value.
+const SHORT_WITH_VALUE: &str = "AbcCdDeEFHKmoPQrTuUwxXy";
+
+/// Long options of curl that consume a value.
+const LONG_WITH_VALUE: &[&str] = &[
+    "cacert",
+    "capath",
+    "cert",
+    "connect-timeout",
+    "connect-to",
+    "cookie",
+    "cookie-jar",
+    "data",
+    "data-ascii",
+    "data-binary",
+    "data-raw",
+    "data-urlencode",
+    "form",
+    "form-string",
+    "header",
+    "key",
+    "max-time",
+    "output",
+    "proxy",
+    "range",
+    "referer",
+    "request",
+    "resolve",
+    "retry",
+    "url",
+    "user",
+    "user-agent",
+    "write-out",
+];
+
+fn import_error(reason: &str) -> RustamanError {
+    RustamanError::ImportError(format!("Invalid curl command: {}", reason))
+}
+
+/// Read the `$'...'` quoted strings of bash.
+fn read_ansi_c_quoted(chars: &mut Peekable<Chars>, word: &mut String) -> RustamanResult<()> {
+    loop {
+        match chars.next() {
+            Some('\'') => return Ok(()),
+            Some('\\') => match chars.next() {
+                Some('n') => word.push('\n'),
+                Some('r') => word.push('\r'),
+                Some('t') => word.push('\t'),
+                Some(c @ ('x' | 'u' | 'U')) => {
+                    let len = match c {
+                        'x' => 2,
+                        'u' => 4,
+                        _ => 8,
+                    };
+                    let mut hex = String::new();
+                    while hex.len() < len {
+                        match chars.peek() {
+                            Some(h) if h.is_ascii_hexdigit() => hex.push(chars.next().unwrap()),
+                            _ => break,
+                        }
+                    }
+                    let decoded = u32::from_str_radix(hex.as_str(), 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| import_error("invalid escape sequence"))?;
+                    word.push(decoded);
+                }
+                Some(c) => word.push(c),
+                None => return Err(import_error("unterminated quote")),
+            },
+            Some(c) => word.push(c),
+            None => return Err(import_error("unterminated quote")),
+        }
+    }
+}
+
+/// Split the command line in words, like a posix shell does.
+fn split_command(command: &str) -> RustamanResult<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                // line continuation
+                Some('\n') => {}
+                Some('\r') if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                }
+                Some(c) => {
+                    word.push(c);
+                    in_word = true;
+                }
+                None => {}
+            },
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(import_error("unterminated quote")),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err(import_error("unterminated quote")),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(import_error("unterminated quote")),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                in_word = true;
+                read_ansi_c_quoted(&mut chars, &mut word)?;
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+fn urlencode(value: &str) -> String {
+    form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+/// Encode the value of `--data-urlencode` like curl does.
+fn data_urlencode(value: &str) -> String {
+    if value.starts_with('@') || (value.contains('@') && !value.contains('=')) {
+        // content read from a file, kept as is.
+        return value.to_string();
+    }
+    match value.split_once('=') {
+        Some(("", content)) => urlencode(content),
+        Some((name, content)) => format!("{}={}", name, urlencode(content)),
+        None => urlencode(value),
+    }
+}
+
+/// Read a group of short options, like `-sSLk` or `-kH 'X: y'`.
+///
+/// An option that consumes a value takes the rest of the group, or the next word.
+fn short_options(
+    group: &str,
+    words: &mut impl Iterator<Item = String>,
+) -> Vec<(String, Option<String>)> {
+    let mut options = Vec::new();
+    for (idx, flag) in group.char_indices() {
+        if SHORT_WITH_VALUE.contains(flag) {
+            let rest = &group[idx + flag.len_utf8()..];
+            let value = if rest.is_empty() {
+                words.next()
+            } else {
+                Some(rest.to_string())
+            };
+            options.push((flag.to_string(), value));
+            break;
+        }
+        options.push((flag.to_string(), None));
+    }
+    options
+}
+
+fn has_header(headers: &[(String, String)], name: &str) -> bool {
+    headers
+        .iter()
+        .any(|(key, _)| key.eq_ignore_ascii_case(name))
+}
+
+/// Convert a curl command line to a rustaman template.
+pub fn import(command: &str) -> RustamanResult<ImportedRequest> {
+    let words = split_command(command.trim())?;
+    let mut words = words.into_iter();
+    match words.next() {
+        Some(prog) if prog == "curl" || prog.ends_with("/curl") || prog == "curl.exe" => {}
+        _ => return Err(import_error("the command must start with curl")),
+    }
+
+    let mut method: Option<String> = None;
+    let mut url: Option<String> = None;
+    let mut headers: Vec<(String, String)> = Vec::new();
+    let mut data: Vec<String> = Vec::new();
+    let mut forms: Vec<String> = Vec::new();
+    let mut directives: Vec<String> = Vec::new();
+    let mut get = false;
+    let mut head = false;
+    let mut user: Option<String> = None;
+
+    while let Some(word) = words.next() {
+        let options = if let Some(long) = word.strip_prefix("--") {
+            vec![match long.split_once('=') {
+                Some((option, value)) => (option.to_string(), Some(value.to_string())),
+                None if LONG_WITH_VALUE.contains(&long) => (long.to_string(), words.next()),
+                None => (long.to_string(), None),
+            }]
+        } else if word.len() > 1 && word.starts_with('-') {
+            short_options(&word[1..], &mut words)
+        } else {
+            url = Some(word);
+            continue;
+        };
+
+        for (option, value) in options {
+            let value = value.unwrap_or_default();
+            match option.as_str() {
+                "X" | "request" => method = Some(value.to_uppercase()),
+                "url" => url = Some(value),
+                "H" | "header" => {
+                    if let Some((key, val)) = value.split_once(':') {
+                        headers.push((key.trim().to_string(), val.trim().to_string()));
+                    }
+                }
+                "d" | "data" | "data-ascii" | "data-binary" | "data-raw" => data.push(value),
+                "data-urlencode" => data.push(data_urlencode(value.as_str())),
+                "F" | "form" | "form-string" => forms.push(value),
+                "u" | "user" => user = Some(value),
+                "b" | "cookie" => headers.push(("Cookie".to_string(), value)),
+                "A" | "user-agent" => headers.push(("User-Agent".to_string(), value)),
+                "e" | "referer" => headers.push(("Referer".to_string(), value)),
+                "k" | "insecure" => directives.push("AllowInsecureCertificate".to_string()),
+                "G" | "get" => get = true,
+                "I" | "head" => head = true,
+                "connect-to" => {
+                    // HOST1:PORT1:HOST2:PORT2
+                    let parts: Vec<&str> = value.splitn(3, ':').collect();
+                    if let Some(authority) = parts.get(2) {
+                        directives.push(format!("Authority: {}", authority));
+                    }
+                }
+                "resolve" => {
+                    // HOST:PORT:ADDRESS
+                    let parts: Vec<&str> = value.splitn(3, ':').collect();
+                    if parts.len() == 3 {
+                        directives.push(format!("Authority: {}:{}", parts[2], parts[1]));
+                    }
+                }
+                _ => debug!("Ignoring curl option {}", option),
+            }
+        }
+    }
+
+    let mut url = url.ok_or_else(|| import_error("missing url"))?;
+    let mut comments = vec!["Imported from curl".to_string()];
+    let mut body: Option<String> = None;
+    if get {
+        if !data.is_empty() {
+            url.push(if url.contains('?') { '&' } else { '?' });
+            url.push_str(data.join("&").as_str());
+        }
+    } else if !forms.is_empty() {
+        if !has_header(&headers, "Content-Type") {
+            headers.push((
+                "Content-Type".to_string(),
+                format!("multipart/form-data; boundary={}", MULTIPART_BOUNDARY),
+            ));
+        }
+        body = Some(multipart_body(forms.as_slice(), &mut comments));
+    } else if !data.is_empty() {
+        if !has_header(&headers, "Content-Type") {
+            headers.push((
+                "Content-Type".to_string(),
+                "application/x-www-form-urlencoded".to_string(),
+            ));
+        }
+        body = Some(data.join("&"));
+    }
+    if let Some(user) = user {
+        if !has_header(&headers, "Authorization") {
+            headers.push((
+                "Authorization".to_string(),
+                format!("Basic {}", base64::encode(user.as_bytes())),
+            ));
+        }
+    }
+
+    let method = method.unwrap_or_else(|| {
+        if head {
+            "HEAD".to_string()
+        } else if body.is_some() {
+            "POST".to_string()
+        } else {
+            "GET".to_string()
+        }
+    });
+
+    Ok(ImportedRequest {
+        name: request_name(method.as_str(), url.as_str()),
+        template: build_literal_template(
+            comments.join("\n").as_str(),
+            directives.as_slice(),
+            method.as_str(),
+            url.as_str(),
+            headers.as_slice(),
+            body.as_deref(),
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_command() {
+        assert_eq!(
+            split_command("curl -H 'X-A: b c' \"x\\\"y\" $'a\\nb\\x41' d\\ e \\\n  f").unwrap(),
+            vec!["curl", "-H", "X-A: b c", "x\"y", "a\nbA", "d e", "f"]
+        );
+        assert!(split_command("curl 'unterminated").is_err());
+        assert!(split_command("curl \"unterminated").is_err());
+    }
+
+    #[test]
+    fn test_short_options() {
+        let mut words = vec!["X-A: b".to_string()].into_iter();
+        assert_eq!(
+            short_options("sSkH", &mut words),
+            vec![
+                ("s".to_string(), None),
+                ("S".to_string(), None),
+                ("k".to_string(), None),
+                ("H".to_string(), Some("X-A: b".to_string())),
+            ]
+        );
+        let mut words = vec!["next".to_string()].into_iter();
+        assert_eq!(
+            short_options("XPUT", &mut words),
+            vec![("X".to_string(), Some("PUT".to_string()))]
+        );
+        assert_eq!(words.next(), Some("next".to_string()));
+    }
+
+    #[test]
+    fn test_import() {
+        let imported = import(
+            "curl -sSk 'https://example.com/api/users?page=1' \\\n  -H 'Accept: application/json' \\\n  --data-raw '{\"name\":\"{{name}}\"}'",
+        )
+        .unwrap();
+        assert_eq!(imported.name, "POST /api/users");
+        assert_eq!(
+            imported.template,
+            "# Imported from curl\n\
+             #! AllowInsecureCertificate\n\
+             POST https://example.com/api/users?page=1\n\
+             Accept: application/json\n\
+             Content-Type: application/x-www-form-urlencoded\n\
+             \n\
+             {\"name\":\"\\{{name}}\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_import_get_and_user() {
+        let imported = import(
+            "curl -G --data-urlencode 'q=a b' -u user:pass -XHEAD --connect-to ::localhost:8080 http://example.com/search",
+        )
+        .unwrap();
+        assert_eq!(
+            imported.template,
+            "# Imported from curl\n\
+             #! Authority: localhost:8080\n\
+             HEAD http://example.com/search?q=a+b\n\
+             Authorization: Basic dXNlcjpwYXNz\n"
+        );
+    }
+
+    #[test]
+    fn test_import_form() {
+        let imported =
+            import("curl -F name=doc -F file=@/tmp/doc.pdf http://example.com/upload").unwrap();
+        assert!(imported.template.starts_with(
+            "# Imported from curl\n\
+             # WARNING: the file /tmp/doc.pdf of the field file is not embedded, the part is empty\n\
+             POST http://example.com/upload\n\
+             Content-Type: multipart/form-data; boundary=------------------------rustaman\n"
+        ));
+    }
+
+    #[test]
+    fn test_import_error() {
+        assert!(import("wget http://example.com").is_err());
+        assert!(import("curl -s").is_err());
+    }
+}
@@ -0,0 +1,391 @@
+//! Export the traffic in HAR 1.2, and import the HAR files of the browsers.
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value as Json};
+
+use super::{build_literal_template, request_name, ImportedCollection, ImportedRequest};
+use crate::errors::{RustamanError, RustamanResult};
+use crate::helpers::base64;
+use crate::helpers::http::HttpResponse;
+use crate::helpers::httpparser::HttpRequest;
+
+/// Headers computed by the http client, they are not imported.
+const SKIPPED_HEADERS: &[&str] = &["content-length", "host", "connection"];
+
+/// A request sent, and its response or its error.
+#[derive(Debug, Clone)]
+pub struct Exchange {
+    pub started: SystemTime,
+    pub request: HttpRequest,
+    pub response: Option<HttpResponse>,
+    pub error: Option<String>,
+}
+
+impl Exchange {
+    pub fn new(request: HttpRequest) -> Self {
+        Exchange {
+            started: SystemTime::now(),
+            request,
+            response: None,
+            error: None,
+        }
+    }
+}
+
+fn import_error(reason: &str) -> RustamanError {
+    RustamanError::ImportError(format!("Invalid HAR file: {}", reason))
+}
+
+/// Format the time in ISO 8601, in UTC.
+pub fn iso8601(time: SystemTime) -> String {
+    let elapsed = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = elapsed.as_secs();
+    let (hour, min, sec) = ((secs / 3600) % 24, (secs / 60) % 60, secs % 60);
+    // civil from days, http://howardhinnant.github.io/date_algorithms.html
+    let z = (secs / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        hour,
+        min,
+        sec,
+        elapsed.subsec_millis()
+    )
+}
+
+fn name_values<'a, I>(pairs: I) -> Json
+where
+    I: Iterator<Item = (&'a str, &'a str)>,
+{
+    Json::Array(
+        pairs
+            .map(|(name, value)| json!({"name": name.trim(), "value": value.trim()}))
+            .collect(),
+    )
+}
+
+fn mime_type<'a, I>(mut headers: I) -> &'a str
+where
+    I: Iterator<Item = (&'a str, &'a str)>,
+{
+    headers
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("Content-Type"))
+        .map(|(_, value)| value.trim())
+        .unwrap_or("")
+}
+
+fn har_request(request: &HttpRequest) -> Json {
+    let headers = || {
+        request
+            .headers()
+            .iter()
+            .map(|(key, val)| (key.as_str(), val.as_str()))
+    };
+    let query = match url::Url::parse(request.url()) {
+        Ok(url) => Json::Array(
+            url.query_pairs()
+                .map(|(name, value)| json!({"name": name, "value": value}))
+                .collect(),
+        ),
+        Err(_) => Json::Array(vec![]),
+    };
+    let body = request.body().unwrap_or_default();
+    let mut har = json!({
+        "method": request.method().as_str(),
+        "url": request.url(),
+        "httpVersion": request
+            .version()
+            .map(|version| format!("{:?}", version))
+            .unwrap_or_else(|| "HTTP/1.1".to_string()),
+        "cookies": [],
+        "headers": name_values(headers()),
+        "queryString": query,
+        "headersSize": -1,
+        "bodySize": body.len(),
+    });
+    if !body.is_empty() {
+        har["postData"] = json!({
+            "mimeType": mime_type(headers()),
+            "text": body,
+        });
+    }
+    har
+}
+
+fn har_response(response: Option<&HttpResponse>, error: Option<&str>) -> Json {
+    match response {
+        Some(response) => {
+            let headers = || {
+                response
+                    .headers()
+                    .iter()
+                    .map(|(key, val)| (key.as_str(), val.as_str()))
+            };
+            let mut content = json!({
+                "size": response.raw_body().len(),
+                "mimeType": mime_type(headers()),
+                "text": response.body(),
+            });
+            if response.is_binary() {
+                content["text"] = Json::String(base64::encode(response.raw_body()));
+                content["encoding"] = Json::String("base64".to_string());
+            }
+            json!({
+                "status": response.status(),
+                "statusText": response.reason(),
+                "httpVersion": response.version(),
+                "cookies": [],
+                "headers": name_values(headers()),
+                "content": content,
+                "redirectURL": response.header("Location").unwrap_or(""),
+                "headersSize": -1,
+                "bodySize": response.raw_body().len(),
+            })
+        }
+        // HAR has no place for the errors, the browsers use the status 0.
+        None => json!({
+            "status": 0,
+            "statusText": "",
+            "httpVersion": "",
+            "cookies": [],
+            "headers": [],
+            "content": {"size": 0, "mimeType": ""},
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": -1,
+            "_error": error.unwrap_or("No response"),
+        }),
+    }
+}
+
+fn har_timings(response: Option<&HttpResponse>, elapsed: f64) -> Json {
+    let ms = |duration: std::time::Duration| duration.as_secs_f64() * 1000.0;
+    match response.and_then(|response| response.timing()) {
+        // the TLS handshake is included in the connect time, as HAR expects.
+        Some(timing) => json!({
+            "blocked": -1,
+            "dns": ms(timing.dns),
+            "connect": ms(timing.connect + timing.tls.unwrap_or_default()),
+            "ssl": timing.tls.map(ms).unwrap_or(-1.0),
+            "send": 0,
+            "wait": ms(timing.wait),
+            "receive": ms(timing.download),
+        }),
+        None => json!({"send": 0, "wait": elapsed, "receive": 0}),
+    }
+}
+
+/// Export the exchanges in HAR 1.2.
+pub fn export(exchanges: &[Exchange]) -> String {
+    let entries: Vec<Json> = exchanges
+        .iter()
+        .map(|exchange| {
+            let elapsed = exchange
+                .response
+                .as_ref()
+                .map(|response| response.elapsed().as_secs_f64() * 1000.0)
+                .unwrap_or(0.0);
+            // HAR expects the time to be the sum of the timings.
+            let time = exchange
+                .response
+                .as_ref()
+                .and_then(|response| response.timing())
+                .map(|timing| timing.total().as_secs_f64() * 1000.0)
+                .unwrap_or(elapsed);
+            json!({
+                "startedDateTime": iso8601(exchange.started),
+                "time": time,
+                "request": har_request(&exchange.request),
+                "response": har_response(exchange.response.as_ref(), exchange.error.as_deref()),
+                "cache": {},
+                "timings": har_timings(exchange.response.as_ref(), elapsed),
+            })
+        })
+        .collect();
+    let har = json!({
+        "log": {
+            "version": "1.2",
+            "creator": {"name": "rustaman", "version": env!("CARGO_PKG_VERSION")},
+            "entries": entries,
+        }
+    });
+    serde_json::to_string_pretty(&har).unwrap_or_default()
+}
+
+/// Tell if the file content is a HAR.
+pub fn is_har(content: &str) -> bool {
+    match serde_json::from_str::<Json>(content) {
+        Ok(doc) => doc["log"]["entries"].is_array(),
+        Err(_) => false,
+    }
+}
+
+fn import_entry(entry: &Json) -> Option<ImportedRequest> {
+    let request = entry.get("request")?;
+    let method = request.get("method")?.as_str()?;
+    let url = request.get("url")?.as_str()?;
+    let headers: Vec<(String, String)> = match request.get("headers") {
+        Some(Json::Array(headers)) => headers
+            .iter()
+            .filter_map(|header| {
+                let name = header.get("name")?.as_str()?;
+                let value = header.get("value")?.as_str()?;
+                // the pseudo headers of HTTP/2, like :authority
+                if name.starts_with(':') || SKIPPED_HEADERS.contains(&name.to_lowercase().as_str())
+                {
+                    return None;
+                }
+                Some((name.to_string(), value.to_string()))
+            })
+            .collect(),
+        _ => vec![],
+    };
+    let post_data = &request["postData"];
+    let body = match post_data.get("text").and_then(|text| text.as_str()) {
+        Some(text) => Some(text.to_string()),
+        None => match post_data.get("params") {
+            Some(Json::Array(params)) => Some(
+                params
+                    .iter()
+                    .map(|param| {
+                        format!(
+                            "{}={}",
+                            param["name"].as_str().unwrap_or(""),
+                            param["value"].as_str().unwrap_or("")
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join("&"),
+            ),
+            _ => None,
+        },
+    };
+    let body = body.filter(|body| !body.is_empty());
+    let comment = match entry["startedDateTime"].as_str() {
+        Some(started) => format!("Imported from HAR, sent at {}", started),
+        None => "Imported from HAR".to_string(),
+    };
+    Some(ImportedRequest {
+        name: request_name(method, url),
+        template: build_literal_template(
+            comment.as_str(),
+            &[],
+            method,
+            url,
+            headers.as_slice(),
+            body.as_deref(),
+        ),
+    })
+}
+
+/// Import the requests of a HAR file, the responses are ignored.
+pub fn import(content: &str) -> RustamanResult<ImportedCollection> {
+    let doc: Json =
+        serde_json::from_str(content).map_err(|err| import_error(err.to_string().as_str()))?;
+    let entries = doc["log"]["entries"]
+        .as_array()
+        .ok_or_else(|| import_error("missing log entries"))?;
+    let mut collection = ImportedCollection::default();
+    for entry in entries.iter() {
+        match import_entry(entry) {
+            Some(request) => collection.requests.push(request),
+            None => warn!("Ignoring invalid HAR entry"),
+        }
+    }
+    Ok(collection)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    const HAR: &str = r#"{
+        "log": {
+            "version": "1.2",
+            "entries": [{
+                "startedDateTime": "2024-02-29T10:00:00.000Z",
+                "request": {
+                    "method": "POST",
+                    "url": "https://example.com/api/users",
+                    "headers": [
+                        {"name": ":authority", "value": "example.com"},
+                        {"name": "Host", "value": "example.com"},
+                        {"name": "Content-Type", "value": "application/json"}
+                    ],
+                    "postData": {"mimeType": "application/json", "text": "{\"tpl\": \"{{x}}\"}"}
+                }
+            }, {
+                "request": {
+                    "method": "POST",
+                    "url": "https://example.com/login",
+                    "headers": [],
+                    "postData": {"params": [{"name": "user", "value": "john"}, {"name": "next", "value": "/"}]}
+                }
+            }, {
+                "request": {"url": "https://example.com/no-method"}
+            }]
+        }
+    }"#;
+
+    #[test]
+    fn test_iso8601() {
+        assert_eq!(iso8601(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+        assert_eq!(
+            iso8601(UNIX_EPOCH + Duration::from_millis(951_782_400_123)),
+            "2000-02-29T00:00:00.123Z"
+        );
+        assert_eq!(
+            iso8601(UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+            "2023-11-14T22:13:20.000Z"
+        );
+    }
+
+    #[test]
+    fn test_is_har() {
+        assert!(is_har(HAR));
+        assert!(!is_har(r#"{"log": {}}"#));
+        assert!(!is_har("not json"));
+    }
+
+    #[test]
+    fn test_import() {
+        let collection = import(HAR).unwrap();
+        // the entry without method is ignored
+        assert_eq!(collection.requests.len(), 2);
+        assert_eq!(collection.requests[0].name, "POST /api/users");
+        assert_eq!(
+            collection.requests[0].template,
+            "# Imported from HAR, sent at 2024-02-29T10:00:00.000Z\n\
+             POST https://example.com/api/users\n\
+             Content-Type: application/json\n\
+             \n\
+             {\"tpl\": \"\\{{x}}\"}\n"
+        );
+        assert_eq!(
+            collection.requests[1].template,
+            "# Imported from HAR\n\
+             POST https://example.com/login\n\
+             \n\
+             user=john&next=/\n"
+        );
+        assert!(collection.environments.is_empty());
+    }
+
+    #[test]
+    fn test_import_error() {
+        assert!(import("not json").is_err());
+        assert!(import(r#"{"log": {}}"#).is_err());
+    }
+}
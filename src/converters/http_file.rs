@@ -0,0 +1,468 @@
+//! Import and export the `.http` files of VS Code REST Client and JetBrains.
+//!
+//! ```text
+//! @baseUrl = https://api.example.com
+//!
+//! ### Get the users
+//! GET {{baseUrl}}/users
+//! Accept: application/json
+//! ```
+//!
+//! The requests are separated by `###` and the `@variable` declarations are
+//! mapped to an environment. The requests of a block chained by
+//! `#! EndCapture` are kept in one template.
+use std::str::FromStr;
+
+use reqwest::Method;
+use serde_json::Value as Json;
+
+use super::{
+    build_environment, request_name, ImportedCollection, ImportedEnvironment, ImportedRequest,
+};
+use crate::errors::{RustamanError, RustamanResult};
+use crate::helpers::httpparser::RE_SPLIT_END_CAPTURE;
+use crate::models::{Environment, Workspace};
+
+/// Any uppercase token is a method, as in the request templates, like PROPFIND.
+fn is_method(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_uppercase()) && Method::from_str(word).is_ok()
+}
+
+fn import_error(reason: &str) -> RustamanError {
+    RustamanError::ImportError(format!("Invalid http file: {}", reason))
+}
+
+/// Parse the `@name = value` declaration.
+fn variable(line: &str) -> Option<(String, String)> {
+    let (name, value) = line.strip_prefix('@')?.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((name.to_string(), value.trim().to_string()))
+}
+
+fn comment(line: &str) -> Option<&str> {
+    line.strip_prefix('#')
+        .or_else(|| line.strip_prefix("//"))
+        .map(|c| c.trim())
+}
+
+/// Convert a block of the file, return None if the block only declares variables.
+fn import_block(
+    title: &str,
+    lines: &[&str],
+    variables: &mut Vec<(String, String)>,
+) -> Option<ImportedRequest> {
+    let mut template = String::new();
+    let mut name = title.trim().to_string();
+    let mut lines = lines.iter().map(|line| line.trim_end()).peekable();
+
+    // comments and variables, before the request line
+    let mut request_line = None;
+    for line in lines.by_ref() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some((key, value)) = variable(line.trim()) {
+            variables.push((key, value));
+        } else if line.trim().starts_with("#!") {
+            // rustaman directives
+            template.push_str(line.trim());
+            template.push('\n');
+        } else if let Some(text) = comment(line.trim()) {
+            if let Some(request_name) = text.strip_prefix("@name") {
+                if name.is_empty() {
+                    name = request_name
+                        .trim()
+                        .trim_start_matches('=')
+                        .trim()
+                        .to_string();
+                }
+            } else {
+                template.push_str("# ");
+                template.push_str(text);
+                template.push('\n');
+            }
+        } else {
+            request_line = Some(line.trim());
+            break;
+        }
+    }
+    let request_line = request_line?;
+
+    let (method, mut target) = match request_line.split_once(' ') {
+        Some((first, rest)) if is_method(first) && !rest.trim().is_empty() => {
+            (first.to_string(), rest.trim().to_string())
+        }
+        _ => ("GET".to_string(), request_line.to_string()),
+    };
+    // multiline query strings
+    while let Some(line) = lines.peek() {
+        let query = line.trim();
+        if query.starts_with('?') || query.starts_with('&') {
+            target.push_str(query);
+            lines.next();
+        } else {
+            break;
+        }
+    }
+    template.push_str(method.as_str());
+    template.push(' ');
+    template.push_str(target.as_str());
+    template.push('\n');
+
+    let mut body: Vec<&str> = Vec::new();
+    let mut in_body = false;
+    let mut in_handler = false;
+    for line in lines {
+        if !in_body {
+            if line.trim().is_empty() {
+                in_body = true;
+            } else if comment(line.trim()).is_none() {
+                template.push_str(line.trim());
+                template.push('\n');
+            }
+            continue;
+        }
+        // the response handlers of JetBrains are not supported
+        if in_handler {
+            in_handler = !line.contains("%}");
+            continue;
+        }
+        if line.starts_with("> {%") {
+            in_handler = !line.contains("%}");
+            continue;
+        }
+        if line.starts_with("> ") || line.starts_with("<> ") {
+            continue;
+        }
+        body.push(line);
+    }
+    while body.last().map_or(false, |line| line.trim().is_empty()) {
+        body.pop();
+    }
+    if !body.is_empty() {
+        template.push('\n');
+        template.push_str(body.join("\n").as_str());
+        template.push('\n');
+    }
+
+    if name.is_empty() {
+        let url = target.split(' ').next().unwrap_or("");
+        name = request_name(method.as_str(), url);
+    }
+    Some(ImportedRequest { name, template })
+}
+
+/// Convert a block of the file, its requests chained by `#! EndCapture` are
+/// joined in one template, named by the first one.
+fn import_chain(
+    title: &str,
+    lines: &[&str],
+    variables: &mut Vec<(String, String)>,
+) -> Option<ImportedRequest> {
+    let mut chain: Option<ImportedRequest> = None;
+    for part in lines.split(|line| RE_SPLIT_END_CAPTURE.is_match(line)) {
+        let request = match import_block(title, part, variables) {
+            Some(request) => request,
+            None => continue,
+        };
+        match chain.as_mut() {
+            Some(chain) => {
+                chain.template.push_str("#! EndCapture\n");
+                chain.template.push_str(request.template.as_str());
+            }
+            None => chain = Some(request),
+        }
+    }
+    chain
+}
+
+/// Import a `.http` file, the variables are imported in an environment.
+pub fn import(content: &str, environment_name: &str) -> RustamanResult<ImportedCollection> {
+    let mut collection = ImportedCollection::default();
+    let mut variables = Vec::new();
+    let mut title = "";
+    let mut block: Vec<&str> = Vec::new();
+    for line in content.lines() {
+        if let Some(separator) = line.trim_start().strip_prefix("###") {
+            if let Some(request) = import_chain(title, block.as_slice(), &mut variables) {
+                collection.requests.push(request);
+            }
+            title = separator;
+            block.clear();
+        } else {
+            block.push(line);
+        }
+    }
+    if let Some(request) = import_chain(title, block.as_slice(), &mut variables) {
+        collection.requests.push(request);
+    }
+    if collection.requests.is_empty() {
+        return Err(import_error("no request found"));
+    }
+    if !variables.is_empty() {
+        collection.environments.push(ImportedEnvironment {
+            name: environment_name.to_string(),
+            payload: build_environment(variables.as_slice(), &[])?,
+        });
+    }
+    Ok(collection)
+}
+
+/// Values of a private environment shorter than this are placeholders,
+/// they are not obfuscated, they would be replaced everywhere in the traffic.
+const MIN_SECRET_LEN: usize = 3;
+
+/// Import the `http-client.env.json` environments of JetBrains.
+///
+/// The strings of the private environment file are obfuscated, the numbers
+/// and the booleans are not secrets.
+pub fn import_environments(content: &str, private: bool) -> RustamanResult<ImportedCollection> {
+    let doc: Json =
+        serde_json::from_str(content).map_err(|err| import_error(err.to_string().as_str()))?;
+    let environments = doc
+        .as_object()
+        .ok_or_else(|| import_error("the environments must be an object"))?;
+    let mut collection = ImportedCollection::default();
+    for (name, values) in environments.iter() {
+        let mut variables = Vec::new();
+        let mut secrets = Vec::new();
+        if let Json::Object(values) = values {
+            for (key, value) in values.iter() {
+                let value = match value {
+                    Json::String(s) => {
+                        if private && s.chars().count() >= MIN_SECRET_LEN {
+                            secrets.push(key.clone());
+                        }
+                        s.clone()
+                    }
+                    Json::Object(_) | Json::Array(_) => continue,
+                    v => v.to_string(),
+                };
+                variables.push((key.clone(), value));
+            }
+        }
+        collection.environments.push(ImportedEnvironment {
+            name: name.clone(),
+            payload: build_environment(variables.as_slice(), secrets.as_slice())?,
+        });
+    }
+    Ok(collection)
+}
+
+/// Export the active requests of the workspace in a `.http` file.
+///
+/// The scalar values of the environment are declared as variables, the
+/// obfuscated ones are left empty. A request is one block, its requests
+/// chained by `#! EndCapture` are not split, their captures are kept.
+pub fn export(workspace: &Workspace, environ: &Environment) -> RustamanResult<String> {
+    let mut content = String::new();
+    let payload = environ.parsed_payload()?;
+    let secrets = match payload.get("__obfuscated__") {
+        Some(serde_yaml_ng::Value::Sequence(secrets)) => secrets
+            .iter()
+            .filter_map(|secret| secret.as_str())
+            .collect(),
+        _ => vec![],
+    };
+    if let serde_yaml_ng::Value::Mapping(mapping) = &payload {
+        for (key, value) in mapping.iter() {
+            let key = match key.as_str() {
+                Some("__obfuscated__") | None => continue,
+                Some(key) => key,
+            };
+            let value = match value {
+                serde_yaml_ng::Value::String(s) => s.clone(),
+                serde_yaml_ng::Value::Number(n) => n.to_string(),
+                serde_yaml_ng::Value::Bool(b) => b.to_string(),
+                _ => continue,
+            };
+            if secrets.contains(&key) {
+                content.push_str(format!("# {} is a secret of {}\n", key, environ.name()).as_str());
+                content.push_str(format!("@{} =\n", key).as_str());
+            } else {
+                content.push_str(format!("@{} = {}\n", key, value).as_str());
+            }
+        }
+        if !content.is_empty() {
+            content.push('\n');
+        }
+    }
+
+    for request in workspace.requests().iter() {
+        if !request.active() {
+            continue;
+        }
+        content.push_str(format!("### {}\n", request.name()).as_str());
+        content.push_str(request.template().trim());
+        content.push_str("\n\n");
+    }
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HTTP_FILE: &str = "@baseUrl = https://api.example.com
+@token = abc
+
+### Get the users
+GET {{baseUrl}}/users
+    ?page=1
+    &size=10
+Accept: application/json
+
+> {% client.global.set(\"id\", response.body.id); %}
+
+###
+# @name Search
+PROPFIND {{baseUrl}}/files HTTP/1.1
+Depth: 1
+
+<?xml version=\"1.0\"?>
+<propfind xmlns=\"DAV:\"/>
+
+###
+https://example.com/health
+";
+
+    #[test]
+    fn test_is_method() {
+        assert!(is_method("GET"));
+        assert!(is_method("PROPFIND"));
+        assert!(!is_method("get"));
+        assert!(!is_method("https://example.com"));
+        assert!(!is_method("{{baseUrl}}/users"));
+    }
+
+    #[test]
+    fn test_import() {
+        let collection = import(HTTP_FILE, "requests").unwrap();
+        assert_eq!(collection.requests.len(), 3);
+        assert_eq!(collection.requests[0].name, "Get the users");
+        assert_eq!(
+            collection.requests[0].template,
+            "GET {{baseUrl}}/users?page=1&size=10\nAccept: application/json\n"
+        );
+        assert_eq!(collection.requests[1].name, "Search");
+        assert_eq!(
+            collection.requests[1].template,
+            "PROPFIND {{baseUrl}}/files HTTP/1.1\n\
+             Depth: 1\n\
+             \n\
+             <?xml version=\"1.0\"?>\n\
+             <propfind xmlns=\"DAV:\"/>\n"
+        );
+        assert_eq!(collection.requests[2].name, "GET /health");
+        assert_eq!(
+            collection.requests[2].template,
+            "GET https://example.com/health\n"
+        );
+
+        assert_eq!(collection.environments.len(), 1);
+        let environment = &collection.environments[0];
+        assert_eq!(environment.name, "requests");
+        assert!(environment
+            .payload
+            .contains("baseUrl: https://api.example.com"));
+        assert!(environment.payload.contains("token: abc"));
+    }
+
+    const LOGIN: &str = "#! Capture: token = $.access_token
+POST {{baseUrl}}/login
+Content-Type: application/json
+
+{\"user\": \"admin\"}
+#! EndCapture
+GET {{baseUrl}}/me
+Authorization: Bearer {{token}}
+";
+
+    #[test]
+    fn test_import_chain() {
+        let content = format!(
+            "### Login\n{}\n###\nGET https://example.com/health\n",
+            LOGIN
+        );
+        let collection = import(content.as_str(), "requests").unwrap();
+        assert_eq!(collection.requests.len(), 2);
+        assert_eq!(collection.requests[0].name, "Login");
+        assert_eq!(collection.requests[0].template, LOGIN);
+        assert_eq!(collection.requests[1].name, "GET /health");
+    }
+
+    #[test]
+    fn test_export() {
+        let filepath = std::env::temp_dir().join("rustaman-http-file-export.json");
+        let filepath = filepath.to_str().unwrap();
+        let mut workspace = Workspace::new(filepath);
+        workspace.import_request("Login", LOGIN);
+        workspace.import_request("Health", "GET {{baseUrl}}/health\n");
+        let _ = std::fs::remove_file(filepath);
+        let environ = Environment::new(
+            1,
+            "Dev",
+            "baseUrl: https://api.example.com\ntoken: s3cr3t\n__obfuscated__: [token]\n",
+        );
+        let content = export(&workspace, &environ).unwrap();
+        assert_eq!(
+            content,
+            format!(
+                "@baseUrl = https://api.example.com\n\
+                 # token is a secret of Dev\n\
+                 @token =\n\
+                 \n\
+                 ### Login\n\
+                 {}\n\
+                 ### Health\n\
+                 GET {{{{baseUrl}}}}/health\n\n",
+                LOGIN
+            )
+        );
+
+        let collection = import(content.as_str(), "Dev").unwrap();
+        assert_eq!(collection.requests.len(), 2);
+        assert_eq!(collection.requests[0].name, "Login");
+        assert_eq!(collection.requests[0].template, LOGIN);
+        assert_eq!(collection.requests[1].name, "Health");
+        assert_eq!(collection.requests[1].template, "GET {{baseUrl}}/health\n");
+    }
+
+    #[test]
+    fn test_import_error() {
+        assert!(import("@baseUrl = https://api.example.com\n", "requests").is_err());
+    }
+
+    #[test]
+    fn test_import_environments() {
+        let content = r#"{
+            "dev": {"token": "s3cr3t", "id": "42", "port": 8080, "nested": {"a": 1}},
+            "prod": {"token": "t0k3n"}
+        }"#;
+        let collection = import_environments(content, true).unwrap();
+        assert_eq!(collection.environments.len(), 2);
+        let dev = &collection.environments[0];
+        assert_eq!(dev.name, "dev");
+        let payload: serde_yaml_ng::Value = serde_yaml_ng::from_str(dev.payload.as_str()).unwrap();
+        assert_eq!(payload["port"].as_str(), Some("8080"));
+        assert!(payload.get("nested").is_none());
+        // the short values and the numbers are not obfuscated
+        let mut secrets: Vec<&str> = payload["__obfuscated__"]
+            .as_sequence()
+            .unwrap()
+            .iter()
+            .filter_map(|secret| secret.as_str())
+            .collect();
+        secrets.sort();
+        assert_eq!(secrets, vec!["token"]);
+
+        let collection = import_environments(content, false).unwrap();
+        assert!(!collection.environments[0]
+            .payload
+            .contains("__obfuscated__"));
+        assert!(import_environments("[]", false).is_err());
+    }
+}
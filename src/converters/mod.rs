@@ -0,0 +1,237 @@
+//! Conversions between rustaman templates and other http tools formats.
+pub(crate) mod command;
+pub(crate) mod curl;
+pub(crate) mod har;
+pub(crate) mod http_file;
+pub(crate) mod openapi;
+pub(crate) mod postman;
+pub(crate) mod snippet;
+
+use crate::errors::{RustamanError, RustamanResult};
+use crate::helpers::httpparser::HttpRequest;
+use crate::models::DEFAULT_ENVIRONMENT;
+
+/// A request read from another format, ready to be added to the workspace.
+#[derive(Debug, Clone)]
+pub struct ImportedRequest {
+    pub name: String,
+    pub template: String,
+}
+
+/// An environment read from another format.
+#[derive(Debug, Clone)]
+pub struct ImportedEnvironment {
+    pub name: String,
+    pub payload: String,
+}
+
+/// Everything read from an imported file.
+#[derive(Debug, Clone, Default)]
+pub struct ImportedCollection {
+    pub requests: Vec<ImportedRequest>,
+    pub environments: Vec<ImportedEnvironment>,
+}
+
+/// Import a file, its format is guessed from its content.
+pub fn import_file(filepath: &str) -> RustamanResult<ImportedCollection> {
+    let content = std::fs::read_to_string(filepath)?;
+    let path = std::path::Path::new(filepath);
+    let filename = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if filename.ends_with(".http") || filename.ends_with(".rest") {
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        return http_file::import(content.as_str(), stem.as_str());
+    }
+    if filename.ends_with(".env.json") {
+        return http_file::import_environments(
+            content.as_str(),
+            filename.ends_with(".private.env.json"),
+        );
+    }
+    if har::is_har(content.as_str()) {
+        return har::import(content.as_str());
+    }
+    if postman::is_postman(content.as_str()) {
+        return postman::import(content.as_str());
+    }
+    if openapi::is_openapi(content.as_str()) {
+        return openapi::import(content.as_str());
+    }
+    Err(RustamanError::ImportError(format!(
+        "Unsupported file format: {}",
+        filepath
+    )))
+}
+
+/// Build the YAML payload of an environment, the secrets are obfuscated.
+///
+/// The empty secrets are not obfuscated, there is nothing to hide.
+pub fn build_environment(
+    variables: &[(String, String)],
+    secrets: &[String],
+) -> RustamanResult<String> {
+    let mut mapping = serde_yaml_ng::Mapping::new();
+    for (key, val) in variables.iter() {
+        mapping.insert(
+            serde_yaml_ng::Value::String(key.clone()),
+            serde_yaml_ng::Value::String(val.clone()),
+        );
+    }
+    let secrets: Vec<&String> = secrets
+        .iter()
+        .filter(|secret| {
+            variables
+                .iter()
+                .any(|(key, val)| key == *secret && !val.is_empty())
+        })
+        .collect();
+    if !secrets.is_empty() {
+        mapping.insert(
+            serde_yaml_ng::Value::String("__obfuscated__".to_string()),
+            serde_yaml_ng::Value::Sequence(
+                secrets
+                    .iter()
+                    .map(|secret| serde_yaml_ng::Value::String(secret.to_string()))
+                    .collect(),
+            ),
+        );
+    }
+    let mut payload = DEFAULT_ENVIRONMENT.to_string();
+    if !mapping.is_empty() {
+        payload.push_str(serde_yaml_ng::to_string(&mapping)?.as_str());
+    }
+    Ok(payload)
+}
+
+pub const MULTIPART_BOUNDARY: &str = "------------------------rustaman";
+
+/// Build a rustaman template.
+pub fn build_template(
+    comment: &str,
+    directives: &[String],
+    method: &str,
+    url: &str,
+    headers: &[(String, String)],
+    body: Option<&str>,
+) -> String {
+    let mut template = String::new();
+    for line in comment.lines() {
+        template.push_str("# ");
+        template.push_str(line);
+        template.push('\n');
+    }
+    for directive in directives.iter() {
+        template.push_str("#! ");
+        template.push_str(directive.as_str());
+        template.push('\n');
+    }
+    template.push_str(method);
+    template.push(' ');
+    template.push_str(url);
+    template.push('\n');
+    for (key, val) in headers.iter() {
+        template.push_str(key.as_str());
+        template.push_str(": ");
+        template.push_str(val.as_str());
+        template.push('\n');
+    }
+    if let Some(body) = body {
+        template.push('\n');
+        template.push_str(body);
+        if !body.ends_with('\n') {
+            template.push('\n');
+        }
+    }
+    template
+}
+
+/// Escape the texts that look like handlebars expressions, `\{{` is rendered as `{{`.
+pub fn escape_handlebars(text: &str) -> String {
+    text.replace("{{", "\\{{")
+}
+
+/// Build a rustaman template of a request that is sent as is, like a
+/// curl command line, its texts are escaped so they are not rendered.
+pub fn build_literal_template(
+    comment: &str,
+    directives: &[String],
+    method: &str,
+    url: &str,
+    headers: &[(String, String)],
+    body: Option<&str>,
+) -> String {
+    let headers: Vec<(String, String)> = headers
+        .iter()
+        .map(|(key, val)| (escape_handlebars(key), escape_handlebars(val)))
+        .collect();
+    build_template(
+        comment,
+        directives,
+        method,
+        escape_handlebars(url).as_str(),
+        headers.as_slice(),
+        body.map(escape_handlebars).as_deref(),
+    )
+}
+
+/// Build a multipart body of the `name=value` or `name=@filepath` forms.
+///
+/// The files are not embedded, their parts are left empty and a warning
+/// is added to the comments of the template.
+pub fn multipart_body(forms: &[String], comments: &mut Vec<String>) -> String {
+    let mut body = String::new();
+    for form in forms.iter() {
+        let (name, value) = form.split_once('=').unwrap_or((form.as_str(), ""));
+        body.push_str("--");
+        body.push_str(MULTIPART_BOUNDARY);
+        body.push('\n');
+        if let Some(filepath) = value.strip_prefix('@') {
+            let filename = filepath.rsplit('/').next().unwrap_or(filepath);
+            body.push_str(
+                format!(
+                    "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\n\n",
+                    name, filename
+                )
+                .as_str(),
+            );
+            body.push('\n');
+            comments.push(format!(
+                "WARNING: the file {} of the field {} is not embedded, the part is empty",
+                filepath, name
+            ));
+        } else {
+            body.push_str(
+                format!("Content-Disposition: form-data; name=\"{}\"\n\n", name).as_str(),
+            );
+            body.push_str(value.trim_matches('"'));
+            body.push('\n');
+        }
+    }
+    body.push_str("--");
+    body.push_str(MULTIPART_BOUNDARY);
+    body.push_str("--\n");
+    body
+}
+
+/// Name a request from its method and the path of the url.
+pub fn request_name(method: &str, url: &str) -> String {
+    let path = match url::Url::parse(url) {
+        Ok(parsed) => parsed.path().to_string(),
+        Err(_) => url.to_string(),
+    };
+    format!("{} {}", method, path)
+}
+
+/// The body as written in the template, the parser ends lines with CRLF.
+pub fn template_body(httpreq: &HttpRequest) -> Option<String> {
+    httpreq.body().map(|body| {
+        body.strip_suffix("\r\n")
+            .unwrap_or(body.as_str())
+            .replace("\r\n", "\n")
+    })
+}
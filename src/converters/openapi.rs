@@ -0,0 +1,757 @@
+//! Import the OpenAPI 3 documents, in JSON or YAML.
+//!
+//! One request is created per operation, the parameters are handlebars
+//! variables and the `Dev` environment is seeded with the url of the first
+//! server and the examples of the parameters. The variables of the path and
+//! query parameters are prefixed by the operation, like `getUser_id`.
+//! The credentials of the security schemes are seeded with a placeholder
+//! and obfuscated. The texts of the document are escaped, they are not
+//! handlebars templates.
+use serde_json::Value as Json;
+
+use super::{
+    build_environment, build_template, escape_handlebars, multipart_body, ImportedCollection,
+    ImportedEnvironment, ImportedRequest, MULTIPART_BOUNDARY,
+};
+use crate::errors::{RustamanError, RustamanResult};
+
+const METHODS: &[&str] = &[
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// The relative server urls are resolved against it, the location of the document is unknown.
+const DEFAULT_SERVER: &str = "http://localhost/";
+
+/// The value of the credentials in the environment, to replace.
+const SECRET_PLACEHOLDER: &str = "CHANGE_ME";
+
+/// Stop following the references of deeply nested schemas.
+const MAX_DEPTH: usize = 8;
+
+fn import_error(reason: &str) -> RustamanError {
+    RustamanError::ImportError(format!("Invalid OpenAPI document: {}", reason))
+}
+
+fn as_str<'a>(value: &'a Json, key: &str) -> &'a str {
+    value.get(key).and_then(|v| v.as_str()).unwrap_or("")
+}
+
+fn parse(content: &str) -> Option<Json> {
+    serde_yaml_ng::from_str::<Json>(content).ok()
+}
+
+/// Tell if the file content is an OpenAPI 3 document.
+pub fn is_openapi(content: &str) -> bool {
+    match parse(content) {
+        Some(doc) => as_str(&doc, "openapi").starts_with('3'),
+        None => false,
+    }
+}
+
+/// Follow the local `$ref`, like `#/components/schemas/User`.
+fn resolve<'a>(doc: &'a Json, value: &'a Json) -> &'a Json {
+    let mut value = value;
+    // a reference may target another reference
+    for _ in 0..MAX_DEPTH {
+        match value.get("$ref").and_then(|r| r.as_str()) {
+            Some(reference) => match reference.strip_prefix('#') {
+                Some(pointer) => match doc.pointer(pointer) {
+                    Some(target) => value = target,
+                    None => return &Json::Null,
+                },
+                None => return &Json::Null,
+            },
+            None => break,
+        }
+    }
+    value
+}
+
+/// Build an example value from a schema, the recursive schemas are null.
+fn example(doc: &Json, schema: &Json, seen: &mut Vec<String>) -> Json {
+    match schema.get("$ref").and_then(|r| r.as_str()) {
+        Some(reference) if seen.iter().any(|r| r == reference) || seen.len() > MAX_DEPTH => {
+            Json::Null
+        }
+        Some(reference) => {
+            seen.push(reference.to_string());
+            let value = example_of(doc, resolve(doc, schema), seen);
+            seen.pop();
+            value
+        }
+        None => example_of(doc, schema, seen),
+    }
+}
+
+fn example_of(doc: &Json, schema: &Json, seen: &mut Vec<String>) -> Json {
+    if let Some(example) = schema.get("example") {
+        return example.clone();
+    }
+    if let Some(Json::Array(examples)) = schema.get("examples") {
+        if let Some(example) = examples.first() {
+            return example.clone();
+        }
+    }
+    if let Some(default) = schema.get("default") {
+        return default.clone();
+    }
+    if let Some(Json::Array(values)) = schema.get("enum") {
+        if let Some(value) = values.first() {
+            return value.clone();
+        }
+    }
+    if let Some(Json::Array(schemas)) = schema.get("allOf") {
+        let mut merged = serde_json::Map::new();
+        for schema in schemas.iter() {
+            if let Json::Object(properties) = example(doc, schema, seen) {
+                merged.extend(properties);
+            }
+        }
+        return Json::Object(merged);
+    }
+    for key in ["oneOf", "anyOf"] {
+        if let Some(Json::Array(schemas)) = schema.get(key) {
+            if let Some(schema) = schemas.first() {
+                return example(doc, schema, seen);
+            }
+        }
+    }
+    let kind = match schema.get("type") {
+        Some(Json::String(kind)) => kind.as_str(),
+        // OpenAPI 3.1 nullable types, `type: [string, "null"]`
+        Some(Json::Array(kinds)) => kinds
+            .iter()
+            .filter_map(|kind| kind.as_str())
+            .find(|kind| *kind != "null")
+            .unwrap_or("null"),
+        _ if schema.get("properties").is_some() => "object",
+        _ if schema.get("items").is_some() => "array",
+        _ => "",
+    };
+    match kind {
+        "object" => {
+            let mut object = serde_json::Map::new();
+            if let Some(Json::Object(properties)) = schema.get("properties") {
+                for (name, property) in properties.iter() {
+                    let read_only = resolve(doc, property).get("readOnly");
+                    if read_only.and_then(|r| r.as_bool()) == Some(true) {
+                        continue;
+                    }
+                    object.insert(name.clone(), example(doc, property, seen));
+                }
+            }
+            Json::Object(object)
+        }
+        "array" => match schema.get("items") {
+            Some(items) => Json::Array(vec![example(doc, items, seen)]),
+            None => Json::Array(vec![]),
+        },
+        "string" => Json::String(
+            match as_str(schema, "format") {
+                "date" => "2024-01-01",
+                "date-time" => "2024-01-01T00:00:00Z",
+                "email" => "user@example.com",
+                "uuid" => "00000000-0000-0000-0000-000000000000",
+                "uri" | "url" => "https://example.com",
+                "ipv4" => "127.0.0.1",
+                _ => "string",
+            }
+            .to_string(),
+        ),
+        "integer" => Json::from(0),
+        "number" => Json::from(0.0),
+        "boolean" => Json::Bool(true),
+        _ => Json::Null,
+    }
+}
+
+fn as_text(value: &Json) -> String {
+    match value {
+        Json::String(s) => s.clone(),
+        Json::Null => String::new(),
+        _ => value.to_string(),
+    }
+}
+
+/// The example of the parameter, used to seed the environment.
+fn parameter_example(doc: &Json, parameter: &Json) -> Option<String> {
+    if let Some(example) = parameter.get("example") {
+        return Some(as_text(example));
+    }
+    if let Some(Json::Object(examples)) = parameter.get("examples") {
+        if let Some(example) = examples.values().next() {
+            return Some(as_text(&resolve(doc, example)["value"]));
+        }
+    }
+    let schema = resolve(doc, &parameter["schema"]);
+    schema
+        .get("example")
+        .or_else(|| schema.get("default"))
+        .map(as_text)
+}
+
+/// The parameters of the path, overridden by the parameters of the operation.
+fn parameters<'a>(doc: &'a Json, path_item: &'a Json, operation: &'a Json) -> Vec<&'a Json> {
+    let mut parameters: Vec<&Json> = Vec::new();
+    for source in [path_item, operation] {
+        if let Some(Json::Array(params)) = source.get("parameters") {
+            for param in params.iter() {
+                let param = resolve(doc, param);
+                parameters.retain(|p| {
+                    as_str(p, "name") != as_str(param, "name")
+                        || as_str(p, "in") != as_str(param, "in")
+                });
+                parameters.push(param);
+            }
+        }
+    }
+    parameters
+}
+
+/// Add the credentials of the security requirement, return their variables.
+fn security(
+    doc: &Json,
+    operation: &Json,
+    headers: &mut Vec<(String, String)>,
+    query: &mut Vec<String>,
+) -> Vec<&'static str> {
+    let requirements = operation.get("security").or_else(|| doc.get("security"));
+    // the first requirement is enough to authenticate
+    let requirement = match requirements {
+        Some(Json::Array(requirements)) => match requirements.first() {
+            Some(Json::Object(requirement)) => requirement,
+            _ => return vec![],
+        },
+        _ => return vec![],
+    };
+    let mut secrets = Vec::new();
+    for name in requirement.keys() {
+        let scheme = resolve(doc, &doc["components"]["securitySchemes"][name]);
+        match (
+            as_str(scheme, "type"),
+            as_str(scheme, "scheme").to_lowercase().as_str(),
+        ) {
+            ("http", "bearer") | ("oauth2", _) | ("openIdConnect", _) => {
+                headers.push(("Authorization".to_string(), "Bearer {{token}}".to_string()));
+                secrets.push("token");
+            }
+            ("http", "basic") => {
+                headers.push((
+                    "Authorization".to_string(),
+                    "Basic {{#base64}}{{username}}:{{password}}{{/base64}}".to_string(),
+                ));
+                secrets.extend(["username", "password"]);
+            }
+            ("apiKey", _) => {
+                secrets.push("apiKey");
+                let key = as_str(scheme, "name");
+                match as_str(scheme, "in") {
+                    "query" => query.push(format!("{}={{{{apiKey}}}}", key)),
+                    "cookie" => {
+                        headers.push(("Cookie".to_string(), format!("{}={{{{apiKey}}}}", key)))
+                    }
+                    _ => headers.push((key.to_string(), "{{apiKey}}".to_string())),
+                }
+            }
+            _ => {}
+        }
+    }
+    secrets
+}
+
+fn form_value(value: &Json) -> String {
+    url::form_urlencoded::byte_serialize(as_text(value).as_bytes()).collect()
+}
+
+/// The example body of the operation, and its content type.
+fn request_body(
+    doc: &Json,
+    operation: &Json,
+    comments: &mut Vec<String>,
+) -> Option<(String, String)> {
+    let body = resolve(doc, operation.get("requestBody")?);
+    let content = body.get("content")?.as_object()?;
+    let (content_type, media) = content
+        .iter()
+        .find(|(content_type, _)| content_type.contains("json"))
+        .or_else(|| content.iter().next())?;
+
+    let value = if let Some(example) = media.get("example") {
+        example.clone()
+    } else if let Some(Json::Object(examples)) = media.get("examples") {
+        resolve(doc, examples.values().next()?)["value"].clone()
+    } else {
+        example(doc, &media["schema"], &mut Vec::new())
+    };
+
+    let content = if content_type.contains("json") {
+        serde_json::to_string_pretty(&value).unwrap_or_default()
+    } else if content_type == "application/x-www-form-urlencoded" {
+        match &value {
+            Json::Object(fields) => fields
+                .iter()
+                .map(|(key, val)| format!("{}={}", key, form_value(val)))
+                .collect::<Vec<String>>()
+                .join("&"),
+            _ => as_text(&value),
+        }
+    } else if content_type.starts_with("multipart/") {
+        let forms: Vec<String> = match &value {
+            Json::Object(fields) => fields
+                .iter()
+                .map(|(key, val)| format!("{}={}", key, as_text(val)))
+                .collect(),
+            _ => vec![],
+        };
+        return Some((
+            format!("multipart/form-data; boundary={}", MULTIPART_BOUNDARY),
+            multipart_body(forms.as_slice(), comments),
+        ));
+    } else {
+        as_text(&value)
+    };
+    Some((content_type.clone(), content))
+}
+
+/// The content type of the first successful response.
+fn accept(doc: &Json, operation: &Json) -> Option<String> {
+    let responses = operation.get("responses")?.as_object()?;
+    for (status, response) in responses.iter() {
+        if !status.starts_with('2') {
+            continue;
+        }
+        let response = resolve(doc, response);
+        if let Some(Json::Object(content)) = response.get("content") {
+            return content.keys().next().cloned();
+        }
+    }
+    None
+}
+
+/// A handlebars identifier, the other characters are replaced by `_`.
+fn identifier(text: &str) -> String {
+    text.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<&str>>()
+        .join("_")
+}
+
+/// The prefix of the variables of the operation, the parameters of the
+/// operations share their names, like `id`.
+fn variable_prefix(operation: &Json, method: &str, path: &str) -> String {
+    match as_str(operation, "operationId") {
+        "" => identifier(format!("{} {}", method.to_lowercase(), path).as_str()),
+        operation_id => identifier(operation_id),
+    }
+}
+
+/// The path as a template, the `{name}` parameters are replaced by their variable.
+fn path_template(path: &str, prefix: &str, variables: &[(&str, String)]) -> String {
+    let mut template = String::new();
+    let mut rest = path;
+    while let Some((before, after)) = rest.split_once('{') {
+        template.push_str(before);
+        match after.split_once('}') {
+            Some((name, after)) => {
+                let variable = match variables.iter().find(|(param, _)| *param == name) {
+                    Some((_, variable)) => variable.clone(),
+                    None => format!("{}_{}", prefix, identifier(name)),
+                };
+                template.push_str(format!("{{{{{}}}}}", variable).as_str());
+                rest = after;
+            }
+            None => {
+                template.push('{');
+                rest = after;
+            }
+        }
+    }
+    template.push_str(rest);
+    template
+}
+
+fn import_operation(
+    doc: &Json,
+    path: &str,
+    method: &str,
+    path_item: &Json,
+    operation: &Json,
+    warnings: &[String],
+    variables: &mut Vec<(String, String)>,
+    secrets: &mut Vec<String>,
+) -> ImportedRequest {
+    let method = method.to_uppercase();
+    let mut comments = vec!["Imported from OpenAPI".to_string()];
+    comments.extend_from_slice(warnings);
+    for key in ["summary", "description"] {
+        let text = as_str(operation, key).trim();
+        if !text.is_empty() {
+            comments.push(text.to_string());
+        }
+    }
+
+    let prefix = variable_prefix(operation, method.as_str(), path);
+    let mut path_variables: Vec<(&str, String)> = Vec::new();
+    let mut query: Vec<String> = Vec::new();
+    let mut optional: Vec<&str> = Vec::new();
+    let mut headers: Vec<(String, String)> = Vec::new();
+    let mut cookies: Vec<String> = Vec::new();
+    for parameter in parameters(doc, path_item, operation).into_iter() {
+        let name = as_str(parameter, "name");
+        let required = parameter.get("required").and_then(|r| r.as_bool()) == Some(true);
+        let variable = match as_str(parameter, "in") {
+            "path" | "query" => format!("{}_{}", prefix, identifier(name)),
+            _ => name.to_string(),
+        };
+        match as_str(parameter, "in") {
+            "query" if required => query.push(format!("{}={{{{{}}}}}", name, variable)),
+            "query" => {
+                optional.push(name);
+                continue;
+            }
+            "header" => headers.push((name.to_string(), format!("{{{{{}}}}}", variable))),
+            "cookie" => cookies.push(format!("{}={{{{{}}}}}", name, variable)),
+            "path" => path_variables.push((name, variable.clone())),
+            _ => continue,
+        }
+        if !variables.iter().any(|(key, _)| *key == variable) {
+            let value = parameter_example(doc, parameter).unwrap_or_default();
+            variables.push((variable, value));
+        }
+    }
+    let mut url = format!(
+        "{{{{baseUrl}}}}{}",
+        path_template(path, prefix.as_str(), path_variables.as_slice())
+    );
+    if !optional.is_empty() {
+        comments.push(format!(
+            "Optional query parameters: {}",
+            optional.join(", ")
+        ));
+    }
+    for secret in security(doc, operation, &mut headers, &mut query) {
+        if !secrets.iter().any(|name| name == secret) {
+            secrets.push(secret.to_string());
+        }
+        if !variables.iter().any(|(key, _)| key == secret) {
+            variables.push((secret.to_string(), SECRET_PLACEHOLDER.to_string()));
+        }
+    }
+    if !cookies.is_empty() {
+        headers.push(("Cookie".to_string(), cookies.join("; ")));
+    }
+    if !query.is_empty() {
+        url.push('?');
+        url.push_str(query.join("&").as_str());
+    }
+    if let Some(accept) = accept(doc, operation) {
+        headers.push(("Accept".to_string(), accept));
+    }
+    let body = request_body(doc, operation, &mut comments).map(|(content_type, body)| {
+        headers.push(("Content-Type".to_string(), content_type));
+        escape_handlebars(body.as_str())
+    });
+
+    let name = match (
+        as_str(operation, "summary"),
+        as_str(operation, "operationId"),
+    ) {
+        ("", "") => format!("{} {}", method, path),
+        ("", operation_id) => operation_id.to_string(),
+        (summary, _) => summary.to_string(),
+    };
+    // the tags are used like folders
+    let name = match operation["tags"].get(0).and_then(|tag| tag.as_str()) {
+        Some(tag) => format!("{} / {}", tag, name),
+        None => name,
+    };
+
+    ImportedRequest {
+        name,
+        template: build_template(
+            escape_handlebars(comments.join("\n").as_str()).as_str(),
+            &[],
+            method.as_str(),
+            url.as_str(),
+            headers.as_slice(),
+            body.as_deref(),
+        ),
+    }
+}
+
+/// The url of the first server, with the default values of its variables.
+///
+/// A relative url, like `/api`, is resolved against `DEFAULT_SERVER` with a warning.
+fn server_url(doc: &Json, warnings: &mut Vec<String>) -> String {
+    let server = &doc["servers"][0];
+    let mut url = as_str(server, "url").to_string();
+    if let Some(Json::Object(variables)) = server.get("variables") {
+        for (name, variable) in variables.iter() {
+            url = url.replace(
+                format!("{{{}}}", name).as_str(),
+                as_str(variable, "default"),
+            );
+        }
+    }
+    if url::Url::parse(url.as_str()).is_err() {
+        let resolved = url::Url::parse(DEFAULT_SERVER)
+            .and_then(|base| base.join(url.as_str()))
+            .map(|resolved| resolved.to_string())
+            .unwrap_or_else(|_| DEFAULT_SERVER.to_string());
+        warnings.push(format!(
+            "WARNING: the server url {:?} is relative, the baseUrl is {}",
+            url, resolved
+        ));
+        url = resolved;
+    }
+    url.trim_end_matches('/').to_string()
+}
+
+/// Import the operations of an OpenAPI 3 document.
+pub fn import(content: &str) -> RustamanResult<ImportedCollection> {
+    let doc = parse(content).ok_or_else(|| import_error("neither JSON nor YAML"))?;
+    if !as_str(&doc, "openapi").starts_with('3') {
+        return Err(import_error("only OpenAPI 3 is supported"));
+    }
+    let paths = doc
+        .get("paths")
+        .and_then(|paths| paths.as_object())
+        .ok_or_else(|| import_error("missing paths"))?;
+
+    let mut collection = ImportedCollection::default();
+    let mut warnings = Vec::new();
+    let mut variables = vec![("baseUrl".to_string(), server_url(&doc, &mut warnings))];
+    let mut secrets = Vec::new();
+    for (path, path_item) in paths.iter() {
+        let path_item = resolve(&doc, path_item);
+        for method in METHODS.iter() {
+            if let Some(operation) = path_item.get(*method) {
+                collection.requests.push(import_operation(
+                    &doc,
+                    path.as_str(),
+                    method,
+                    path_item,
+                    operation,
+                    warnings.as_slice(),
+                    &mut variables,
+                    &mut secrets,
+                ));
+            }
+        }
+    }
+    collection.environments.push(ImportedEnvironment {
+        name: "Dev".to_string(),
+        payload: build_environment(variables.as_slice(), secrets.as_slice())?,
+    });
+    Ok(collection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = r##"
+openapi: 3.0.3
+servers:
+  - url: https://{region}.example.com/v1/
+    variables:
+      region: {default: eu}
+security:
+  - bearer: []
+components:
+  securitySchemes:
+    bearer: {type: http, scheme: bearer}
+  schemas:
+    User:
+      type: object
+      properties:
+        id: {type: integer, readOnly: true}
+        name: {type: string, example: John}
+        email: {type: string, format: email}
+paths:
+  /users/{id}:
+    parameters:
+      - {name: id, in: path, required: true, example: 42}
+    get:
+      operationId: getUser
+      tags: [users]
+      parameters:
+        - {name: fields, in: query, required: true, example: "name,email"}
+        - {name: debug, in: query}
+      responses:
+        "200":
+          content:
+            application/json: {}
+    put:
+      summary: Update a user
+      requestBody:
+        content:
+          application/json:
+            schema: {$ref: "#/components/schemas/User"}
+  /orders/{id}:
+    get:
+      security: []
+      parameters:
+        - {name: id, in: path, required: true, example: 7}
+"##;
+
+    #[test]
+    fn test_is_openapi() {
+        assert!(is_openapi(DOCUMENT));
+        assert!(!is_openapi("swagger: \"2.0\"\n"));
+        assert!(!is_openapi("{"));
+    }
+
+    #[test]
+    fn test_identifier() {
+        assert_eq!(identifier("get /users/{id}"), "get_users_id");
+        assert_eq!(identifier("filter[name]"), "filter_name");
+    }
+
+    #[test]
+    fn test_import() {
+        let collection = import(DOCUMENT).unwrap();
+        assert_eq!(collection.requests.len(), 3);
+
+        let orders = &collection.requests[0];
+        assert_eq!(orders.name, "GET /orders/{id}");
+        assert_eq!(
+            orders.template,
+            "# Imported from OpenAPI\nGET {{baseUrl}}/orders/{{get_orders_id_id}}\n"
+        );
+
+        let get_user = &collection.requests[1];
+        assert_eq!(get_user.name, "users / getUser");
+        assert_eq!(
+            get_user.template,
+            "# Imported from OpenAPI\n\
+             # Optional query parameters: debug\n\
+             GET {{baseUrl}}/users/{{getUser_id}}?fields={{getUser_fields}}\n\
+             Authorization: Bearer {{token}}\n\
+             Accept: application/json\n"
+        );
+
+        let update_user = &collection.requests[2];
+        assert_eq!(update_user.name, "Update a user");
+        assert!(update_user
+            .template
+            .contains("PUT {{baseUrl}}/users/{{put_users_id_id}}\n"));
+        assert!(update_user
+            .template
+            .contains("Content-Type: application/json\n"));
+        assert!(update_user.template.contains("\"name\": \"John\""));
+        assert!(update_user
+            .template
+            .contains("\"email\": \"user@example.com\""));
+        assert!(!update_user.template.contains("\"id\""));
+    }
+
+    #[test]
+    fn test_import_environment() {
+        let collection = import(DOCUMENT).unwrap();
+        assert_eq!(collection.environments.len(), 1);
+        let environment = &collection.environments[0];
+        assert_eq!(environment.name, "Dev");
+        let payload: serde_yaml_ng::Value =
+            serde_yaml_ng::from_str(environment.payload.as_str()).unwrap();
+        assert_eq!(
+            payload["baseUrl"].as_str(),
+            Some("https://eu.example.com/v1")
+        );
+        assert_eq!(payload["getUser_id"].as_str(), Some("42"));
+        assert_eq!(payload["get_orders_id_id"].as_str(), Some("7"));
+        assert_eq!(payload["getUser_fields"].as_str(), Some("name,email"));
+        assert_eq!(payload["token"].as_str(), Some(SECRET_PLACEHOLDER));
+        assert_eq!(
+            payload["__obfuscated__"],
+            serde_yaml_ng::from_str::<serde_yaml_ng::Value>("[token]").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_import_security() {
+        let collection = import(
+            r##"
+openapi: 3.0.3
+components:
+  securitySchemes:
+    basic: {type: http, scheme: basic}
+    key: {type: apiKey, in: query, name: api_key}
+paths:
+  /login:
+    post:
+      security: [{basic: []}]
+  /search:
+    get:
+      security: [{key: []}]
+"##,
+        )
+        .unwrap();
+        assert!(collection.requests[0]
+            .template
+            .contains("Authorization: Basic {{#base64}}{{username}}:{{password}}{{/base64}}\n"));
+        assert!(collection.requests[1]
+            .template
+            .contains("GET {{baseUrl}}/search?api_key={{apiKey}}\n"));
+        let payload: serde_yaml_ng::Value =
+            serde_yaml_ng::from_str(collection.environments[0].payload.as_str()).unwrap();
+        assert_eq!(payload["username"].as_str(), Some(SECRET_PLACEHOLDER));
+        assert_eq!(payload["password"].as_str(), Some(SECRET_PLACEHOLDER));
+        assert_eq!(payload["apiKey"].as_str(), Some(SECRET_PLACEHOLDER));
+        assert_eq!(
+            payload["__obfuscated__"],
+            serde_yaml_ng::from_str::<serde_yaml_ng::Value>("[username, password, apiKey]")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_import_escape() {
+        let collection = import(
+            r##"
+openapi: 3.0.3
+servers: [{url: "https://example.com"}]
+paths:
+  /render:
+    post:
+      summary: Render {{name}}
+      requestBody:
+        content:
+          text/plain:
+            example: "Hello {{name}}"
+"##,
+        )
+        .unwrap();
+        assert_eq!(
+            collection.requests[0].template,
+            "# Imported from OpenAPI\n\
+             # Render \\{{name}}\n\
+             POST {{baseUrl}}/render\n\
+             Content-Type: text/plain\n\
+             \n\
+             Hello \\{{name}}\n"
+        );
+    }
+
+    #[test]
+    fn test_import_relative_server() {
+        let collection =
+            import("openapi: 3.1.0\nservers: [{url: /api}]\npaths:\n  /health:\n    get: {}\n")
+                .unwrap();
+        assert_eq!(
+            collection.requests[0].template,
+            "# Imported from OpenAPI\n\
+             # WARNING: the server url \"/api\" is relative, the baseUrl is http://localhost/api\n\
+             GET {{baseUrl}}/health\n"
+        );
+        assert!(collection.environments[0]
+            .payload
+            .contains("baseUrl: http://localhost/api"));
+    }
+
+    #[test]
+    fn test_import_error() {
+        assert!(import("swagger: \"2.0\"\npaths: {}\n").is_err());
+        assert!(import("openapi: 3.0.0\n").is_err());
+    }
+}
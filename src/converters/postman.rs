@@ -0,0 +1,486 @@
+//! Import the Postman Collection v2.1 and the Postman environment files.
+//!
+//! The `{{variable}}` of Postman are handlebars expressions, they are kept
+//! as is. The folders are flattened, their names prefix the request names.
+use serde_json::Value as Json;
+use url::form_urlencoded;
+
+use super::{
+    build_environment, build_template, multipart_body, ImportedCollection, ImportedEnvironment,
+    ImportedRequest, MULTIPART_BOUNDARY,
+};
+use crate::errors::{RustamanError, RustamanResult};
+use crate::helpers::base64;
+
+fn import_error(reason: &str) -> RustamanError {
+    RustamanError::ImportError(format!("Invalid Postman file: {}", reason))
+}
+
+fn as_str<'a>(value: &'a Json, key: &str) -> &'a str {
+    value.get(key).and_then(|v| v.as_str()).unwrap_or("")
+}
+
+/// Postman values may be numbers or booleans.
+fn as_string(value: Option<&Json>) -> String {
+    match value {
+        Some(Json::String(s)) => s.clone(),
+        Some(Json::Null) | None => String::new(),
+        Some(v) => v.to_string(),
+    }
+}
+
+fn disabled(value: &Json) -> bool {
+    value
+        .get("disabled")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+fn is_collection(doc: &Json) -> bool {
+    as_str(&doc["info"], "schema").contains("getpostman.com")
+}
+
+fn is_environment(doc: &Json) -> bool {
+    doc.get("_postman_variable_scope").is_some()
+        || (doc.get("name").is_some() && doc.get("values").map_or(false, |v| v.is_array()))
+}
+
+/// Tell if the file content is a Postman collection or environment.
+pub fn is_postman(content: &str) -> bool {
+    match serde_json::from_str::<Json>(content) {
+        Ok(doc) => is_collection(&doc) || is_environment(&doc),
+        Err(_) => false,
+    }
+}
+
+/// Encode the form values, except the handlebars expressions.
+fn urlencode(value: &str) -> String {
+    if value.contains("{{") {
+        value.to_string()
+    } else {
+        form_urlencoded::byte_serialize(value.as_bytes()).collect()
+    }
+}
+
+fn has_header(headers: &[(String, String)], name: &str) -> bool {
+    headers
+        .iter()
+        .any(|(key, _)| key.eq_ignore_ascii_case(name))
+}
+
+fn build_url(url: &Json) -> String {
+    match url {
+        Json::String(raw) => raw.clone(),
+        Json::Object(_) => {
+            let mut raw = as_str(url, "raw").to_string();
+            // path variables, `/:id`
+            if let Some(Json::Array(variables)) = url.get("variable") {
+                for variable in variables.iter() {
+                    let key = as_str(variable, "key");
+                    let value = match as_string(variable.get("value")) {
+                        v if v.is_empty() => format!("{{{{{}}}}}", key),
+                        v => v,
+                    };
+                    raw = raw.replace(
+                        format!("/:{}", key).as_str(),
+                        format!("/{}", value).as_str(),
+                    );
+                }
+            }
+            raw
+        }
+        _ => String::new(),
+    }
+}
+
+fn auth_value(auth: &Json, kind: &str, key: &str) -> String {
+    if let Some(Json::Array(params)) = auth.get(kind) {
+        for param in params.iter() {
+            if as_str(param, "key") == key {
+                return as_string(param.get("value"));
+            }
+        }
+    }
+    String::new()
+}
+
+/// Add the authentication to the headers, or to the url for api keys in query.
+fn apply_auth(
+    auth: &Json,
+    url: &mut String,
+    headers: &mut Vec<(String, String)>,
+    comments: &mut Vec<String>,
+) {
+    let kind = as_str(auth, "type");
+    match kind {
+        "" | "noauth" => {}
+        "bearer" => headers.push((
+            "Authorization".to_string(),
+            format!("Bearer {}", auth_value(auth, kind, "token")),
+        )),
+        "basic" => {
+            let credentials = format!(
+                "{}:{}",
+                auth_value(auth, kind, "username"),
+                auth_value(auth, kind, "password")
+            );
+            let encoded = if credentials.contains("{{") {
+                format!("{{{{#base64}}}}{}{{{{/base64}}}}", credentials)
+            } else {
+                base64::encode(credentials.as_bytes())
+            };
+            headers.push(("Authorization".to_string(), format!("Basic {}", encoded)));
+        }
+        "apikey" => {
+            let key = auth_value(auth, kind, "key");
+            let value = auth_value(auth, kind, "value");
+            if auth_value(auth, kind, "in") == "query" {
+                url.push(if url.contains('?') { '&' } else { '?' });
+                url.push_str(format!("{}={}", urlencode(&key), urlencode(&value)).as_str());
+            } else {
+                headers.push((key, value));
+            }
+        }
+        _ => comments.push(format!(
+            "The Postman {} authentication is not imported",
+            kind
+        )),
+    }
+}
+
+fn build_body(
+    body: &Json,
+    headers: &mut Vec<(String, String)>,
+    comments: &mut Vec<String>,
+) -> Option<String> {
+    let mut content_type = None;
+    let content = match as_str(body, "mode") {
+        "raw" => {
+            content_type = match as_str(&body["options"]["raw"], "language") {
+                "json" => Some("application/json"),
+                "xml" => Some("application/xml"),
+                "html" => Some("text/html"),
+                "javascript" => Some("application/javascript"),
+                _ => None,
+            };
+            as_str(body, "raw").to_string()
+        }
+        "urlencoded" => {
+            content_type = Some("application/x-www-form-urlencoded");
+            let params: Vec<String> = match body.get("urlencoded") {
+                Some(Json::Array(params)) => params
+                    .iter()
+                    .filter(|param| !disabled(param))
+                    .map(|param| {
+                        format!(
+                            "{}={}",
+                            urlencode(as_str(param, "key")),
+                            urlencode(as_string(param.get("value")).as_str())
+                        )
+                    })
+                    .collect(),
+                _ => vec![],
+            };
+            params.join("&")
+        }
+        "formdata" => {
+            let forms: Vec<String> = match body.get("formdata") {
+                Some(Json::Array(params)) => params
+                    .iter()
+                    .filter(|param| !disabled(param))
+                    .map(|param| {
+                        if as_str(param, "type") == "file" {
+                            format!("{}=@{}", as_str(param, "key"), as_string(param.get("src")))
+                        } else {
+                            format!("{}={}", as_str(param, "key"), as_string(param.get("value")))
+                        }
+                    })
+                    .collect(),
+                _ => vec![],
+            };
+            if forms.is_empty() {
+                return None;
+            }
+            if !has_header(headers, "Content-Type") {
+                headers.push((
+                    "Content-Type".to_string(),
+                    format!("multipart/form-data; boundary={}", MULTIPART_BOUNDARY),
+                ));
+            }
+            return Some(multipart_body(forms.as_slice(), comments));
+        }
+        "graphql" => {
+            content_type = Some("application/json");
+            let graphql = &body["graphql"];
+            let variables = serde_json::from_str::<Json>(as_str(graphql, "variables"))
+                .unwrap_or(Json::Object(Default::default()));
+            let mut payload = serde_json::Map::new();
+            payload.insert("query".to_string(), graphql["query"].clone());
+            payload.insert("variables".to_string(), variables);
+            serde_json::to_string_pretty(&Json::Object(payload)).unwrap_or_default()
+        }
+        "" => String::new(),
+        mode => {
+            comments.push(format!("The Postman {} body is not imported", mode));
+            String::new()
+        }
+    };
+    if content.is_empty() {
+        return None;
+    }
+    if let Some(content_type) = content_type {
+        if !has_header(headers, "Content-Type") {
+            headers.push(("Content-Type".to_string(), content_type.to_string()));
+        }
+    }
+    Some(content)
+}
+
+fn import_request(name: &str, item: &Json, auth: &Json) -> ImportedRequest {
+    let request = &item["request"];
+    let mut comments = vec!["Imported from Postman".to_string()];
+    let description = match request.get("description") {
+        Some(Json::Object(desc)) => desc.get("content").and_then(|c| c.as_str()).unwrap_or(""),
+        Some(Json::String(desc)) => desc.as_str(),
+        _ => "",
+    };
+    if !description.is_empty() {
+        comments.push(description.to_string());
+    }
+
+    let (method, mut url) = match request {
+        // the request may be an url only
+        Json::String(url) => ("GET".to_string(), url.clone()),
+        _ => (
+            match as_str(request, "method") {
+                "" => "GET".to_string(),
+                method => method.to_uppercase(),
+            },
+            build_url(&request["url"]),
+        ),
+    };
+
+    let mut headers: Vec<(String, String)> = match request.get("header") {
+        Some(Json::Array(headers)) => headers
+            .iter()
+            .filter(|header| !disabled(header))
+            .map(|header| {
+                (
+                    as_str(header, "key").to_string(),
+                    as_string(header.get("value")),
+                )
+            })
+            .collect(),
+        _ => vec![],
+    };
+    let auth = match request.get("auth") {
+        Some(request_auth) => request_auth,
+        None => auth,
+    };
+    if !has_header(&headers, "Authorization") {
+        apply_auth(auth, &mut url, &mut headers, &mut comments);
+    }
+    let body = build_body(&request["body"], &mut headers, &mut comments);
+
+    ImportedRequest {
+        name: name.to_string(),
+        template: build_template(
+            comments.join("\n").as_str(),
+            &[],
+            method.as_str(),
+            url.as_str(),
+            headers.as_slice(),
+            body.as_deref(),
+        ),
+    }
+}
+
+/// Walk through the folders, the auth is inherited from the parents.
+fn import_items(items: &Json, prefix: &str, auth: &Json, requests: &mut Vec<ImportedRequest>) {
+    if let Json::Array(items) = items {
+        for item in items.iter() {
+            let name = match prefix {
+                "" => as_str(item, "name").to_string(),
+                _ => format!("{} / {}", prefix, as_str(item, "name")),
+            };
+            let auth = item.get("auth").unwrap_or(auth);
+            if let Some(children) = item.get("item") {
+                import_items(children, name.as_str(), auth, requests);
+            } else if item.get("request").is_some() {
+                requests.push(import_request(name.as_str(), item, auth));
+            }
+        }
+    }
+}
+
+fn import_variables(name: &str, variables: &Json) -> RustamanResult<Option<ImportedEnvironment>> {
+    let mut values = Vec::new();
+    let mut secrets = Vec::new();
+    if let Json::Array(variables) = variables {
+        for variable in variables.iter() {
+            if variable.get("enabled").and_then(|v| v.as_bool()) == Some(false)
+                || disabled(variable)
+            {
+                continue;
+            }
+            let key = as_str(variable, "key").to_string();
+            if as_str(variable, "type") == "secret" {
+                secrets.push(key.clone());
+            }
+            values.push((key, as_string(variable.get("value"))));
+        }
+    }
+    if values.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(ImportedEnvironment {
+        name: name.to_string(),
+        payload: build_environment(values.as_slice(), secrets.as_slice())?,
+    }))
+}
+
+/// Import a Postman collection, or a Postman environment.
+pub fn import(content: &str) -> RustamanResult<ImportedCollection> {
+    let doc: Json =
+        serde_json::from_str(content).map_err(|err| import_error(err.to_string().as_str()))?;
+    let mut collection = ImportedCollection::default();
+    if is_collection(&doc) {
+        import_items(&doc["item"], "", &doc["auth"], &mut collection.requests);
+        if let Some(environment) = import_variables(as_str(&doc["info"], "name"), &doc["variable"])?
+        {
+            collection.environments.push(environment);
+        }
+    } else if is_environment(&doc) {
+        if let Some(environment) = import_variables(as_str(&doc, "name"), &doc["values"])? {
+            collection.environments.push(environment);
+        }
+    } else {
+        return Err(import_error("neither a collection nor an environment"));
+    }
+    Ok(collection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLLECTION: &str = r#"{
+        "info": {
+            "name": "Users",
+            "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json"
+        },
+        "auth": {"type": "bearer", "bearer": [{"key": "token", "value": "{{token}}"}]},
+        "item": [{
+            "name": "Admin",
+            "item": [{
+                "name": "Update user",
+                "request": {
+                    "method": "put",
+                    "header": [
+                        {"key": "Accept", "value": "application/json"},
+                        {"key": "X-Debug", "value": "1", "disabled": true}
+                    ],
+                    "url": {"raw": "{{baseUrl}}/users/:id", "variable": [{"key": "id"}]},
+                    "body": {
+                        "mode": "raw",
+                        "raw": "{\"name\": \"{{name}}\"}",
+                        "options": {"raw": {"language": "json"}}
+                    }
+                }
+            }]
+        }, {
+            "name": "Login",
+            "request": {
+                "method": "POST",
+                "auth": {"type": "basic", "basic": [
+                    {"key": "username", "value": "user"},
+                    {"key": "password", "value": "pass"}
+                ]},
+                "url": "{{baseUrl}}/login",
+                "body": {"mode": "urlencoded", "urlencoded": [{"key": "next", "value": "/home page"}]}
+            }
+        }],
+        "variable": [
+            {"key": "baseUrl", "value": "https://example.com"},
+            {"key": "token", "value": "s3cr3t", "type": "secret"},
+            {"key": "empty", "value": "", "type": "secret"}
+        ]
+    }"#;
+
+    #[test]
+    fn test_is_postman() {
+        assert!(is_postman(COLLECTION));
+        assert!(is_postman(r#"{"name": "Dev", "values": []}"#));
+        assert!(!is_postman(r#"{"openapi": "3.0.0"}"#));
+        assert!(!is_postman("not json"));
+    }
+
+    #[test]
+    fn test_import_collection() {
+        let collection = import(COLLECTION).unwrap();
+        assert_eq!(collection.requests.len(), 2);
+        let update = &collection.requests[0];
+        assert_eq!(update.name, "Admin / Update user");
+        assert_eq!(
+            update.template,
+            "# Imported from Postman\n\
+             PUT {{baseUrl}}/users/{{id}}\n\
+             Accept: application/json\n\
+             Authorization: Bearer {{token}}\n\
+             Content-Type: application/json\n\
+             \n\
+             {\"name\": \"{{name}}\"}\n"
+        );
+        let login = &collection.requests[1];
+        assert_eq!(login.name, "Login");
+        assert_eq!(
+            login.template,
+            "# Imported from Postman\n\
+             POST {{baseUrl}}/login\n\
+             Authorization: Basic dXNlcjpwYXNz\n\
+             Content-Type: application/x-www-form-urlencoded\n\
+             \n\
+             next=%2Fhome+page\n"
+        );
+    }
+
+    #[test]
+    fn test_import_variables() {
+        let collection = import(COLLECTION).unwrap();
+        assert_eq!(collection.environments.len(), 1);
+        let environment = &collection.environments[0];
+        assert_eq!(environment.name, "Users");
+        let payload: serde_yaml_ng::Value =
+            serde_yaml_ng::from_str(environment.payload.as_str()).unwrap();
+        assert_eq!(payload["baseUrl"].as_str(), Some("https://example.com"));
+        // the empty secret is not obfuscated
+        assert_eq!(
+            payload["__obfuscated__"],
+            serde_yaml_ng::from_str::<serde_yaml_ng::Value>("[token]").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_import_environment() {
+        let collection = import(
+            r#"{"name": "Prod", "values": [
+                {"key": "baseUrl", "value": "https://prod.example.com", "enabled": true},
+                {"key": "unused", "value": "x", "enabled": false}
+            ]}"#,
+        )
+        .unwrap();
+        assert!(collection.requests.is_empty());
+        let environment = &collection.environments[0];
+        assert_eq!(environment.name, "Prod");
+        assert!(environment
+            .payload
+            .contains("baseUrl: https://prod.example.com"));
+        assert!(!environment.payload.contains("unused"));
+    }
+
+    #[test]
+    fn test_import_error() {
+        assert!(import("not json").is_err());
+        assert!(import(r#"{"foo": "bar"}"#).is_err());
+    }
+}
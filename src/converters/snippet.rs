@@ -0,0 +1,315 @@
+//! Generate code snippets that send the request from another program.
+use reqwest::Method;
+
+use super::template_body;
+use crate::errors::RustamanResult;
+use crate::helpers::httpparser::{self, HttpRequest};
+use crate::models::Environment;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnippetLanguage {
+    Rust,
+    Python,
+    JavaScript,
+}
+
+/// A string literal for python and javascript, json strings are valid for both.
+fn json_string(value: &str) -> String {
+    serde_json::Value::String(value.to_string()).to_string()
+}
+
+/// A rust string literal.
+fn rust_string(value: &str) -> String {
+    format!("{:?}", value)
+}
+
+/// The headers in the order of the template.
+fn header_pairs(httpreq: &HttpRequest) -> Vec<(&str, &str)> {
+    httpreq
+        .headers()
+        .iter()
+        .map(|(key, val)| (key.as_str(), val.as_str()))
+        .collect()
+}
+
+fn rust_method(method: &Method) -> String {
+    match *method {
+        Method::GET => "reqwest::Method::GET".to_string(),
+        Method::POST => "reqwest::Method::POST".to_string(),
+        Method::PUT => "reqwest::Method::PUT".to_string(),
+        Method::DELETE => "reqwest::Method::DELETE".to_string(),
+        Method::HEAD => "reqwest::Method::HEAD".to_string(),
+        Method::OPTIONS => "reqwest::Method::OPTIONS".to_string(),
+        Method::CONNECT => "reqwest::Method::CONNECT".to_string(),
+        Method::PATCH => "reqwest::Method::PATCH".to_string(),
+        Method::TRACE => "reqwest::Method::TRACE".to_string(),
+        _ => format!(
+            "reqwest::Method::from_bytes({})?",
+            rust_string(method.as_str()).replacen('"', "b\"", 1)
+        ),
+    }
+}
+
+fn rust(httpreq: &HttpRequest) -> String {
+    let mut code = String::new();
+    if let Some((host, port)) = httpreq.authority() {
+        code.push_str(format!("// Authority: {}:{}\n", host, port).as_str());
+    }
+    code.push_str("let client = reqwest::Client::builder()\n");
+    if !httpreq.verify_cert() {
+        code.push_str("    .danger_accept_invalid_certs(true)\n");
+    }
+    code.push_str("    .build()?;\n");
+    code.push_str("let response = client\n");
+    code.push_str(
+        format!(
+            "    .request({}, {})\n",
+            rust_method(&httpreq.method()),
+            rust_string(httpreq.url())
+        )
+        .as_str(),
+    );
+    for (key, val) in header_pairs(httpreq) {
+        code.push_str(
+            format!("    .header({}, {})\n", rust_string(key), rust_string(val)).as_str(),
+        );
+    }
+    if let Some(body) = template_body(httpreq) {
+        code.push_str(format!("    .body({})\n", rust_string(body.as_str())).as_str());
+    }
+    code.push_str("    .send()\n");
+    code.push_str("    .await?;\n");
+    code.push_str("println!(\"{}\", response.text().await?);\n");
+    code
+}
+
+/// The headers of a dict, the values of the repeated headers are combined.
+fn merged_headers(httpreq: &HttpRequest) -> Vec<(&str, String)> {
+    let mut merged: Vec<(&str, String)> = Vec::new();
+    for (key, val) in header_pairs(httpreq) {
+        match merged
+            .iter_mut()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+        {
+            Some((_, merged_val)) => {
+                merged_val.push_str(", ");
+                merged_val.push_str(val);
+            }
+            None => merged.push((key, val.to_string())),
+        }
+    }
+    merged
+}
+
+fn python(httpreq: &HttpRequest) -> String {
+    let mut code = String::new();
+    code.push_str("import requests\n\n");
+    if let Some((host, port)) = httpreq.authority() {
+        code.push_str(format!("# Authority: {}:{}\n", host, port).as_str());
+    }
+    code.push_str("response = requests.request(\n");
+    code.push_str(format!("    {},\n", json_string(httpreq.method().as_str())).as_str());
+    code.push_str(format!("    {},\n", json_string(httpreq.url())).as_str());
+    let headers = merged_headers(httpreq);
+    if !headers.is_empty() {
+        code.push_str("    headers={\n");
+        for (key, val) in headers.iter() {
+            code.push_str(
+                format!("        {}: {},\n", json_string(key), json_string(val)).as_str(),
+            );
+        }
+        code.push_str("    },\n");
+    }
+    if let Some(body) = template_body(httpreq) {
+        code.push_str(format!("    data={},\n", json_string(body.as_str())).as_str());
+    }
+    if !httpreq.verify_cert() {
+        code.push_str("    verify=False,\n");
+    }
+    code.push_str(")\n");
+    code.push_str("print(response.text)\n");
+    code
+}
+
+fn javascript(httpreq: &HttpRequest) -> String {
+    let mut code = String::new();
+    if let Some((host, port)) = httpreq.authority() {
+        code.push_str(format!("// Authority: {}:{}\n", host, port).as_str());
+    }
+    if !httpreq.verify_cert() {
+        code.push_str(
+            "// The certificate is not verified, run node with NODE_TLS_REJECT_UNAUTHORIZED=0\n",
+        );
+    }
+    // the headers are appended, an object literal would merge the repeated ones.
+    let headers = header_pairs(httpreq);
+    if !headers.is_empty() {
+        code.push_str("const headers = new Headers();\n");
+        for (key, val) in headers.iter() {
+            code.push_str(
+                format!(
+                    "headers.append({}, {});\n",
+                    json_string(key),
+                    json_string(val)
+                )
+                .as_str(),
+            );
+        }
+    }
+    code.push_str(
+        format!(
+            "const response = await fetch({}, {{\n",
+            json_string(httpreq.url())
+        )
+        .as_str(),
+    );
+    code.push_str(format!("  method: {},\n", json_string(httpreq.method().as_str())).as_str());
+    if !headers.is_empty() {
+        code.push_str("  headers,\n");
+    }
+    if let Some(body) = template_body(httpreq) {
+        code.push_str(format!("  body: {},\n", json_string(body.as_str())).as_str());
+    }
+    code.push_str("});\n");
+    code.push_str("console.log(await response.text());\n");
+    code
+}
+
+pub fn generate(httpreq: &HttpRequest, language: SnippetLanguage) -> String {
+    match language {
+        SnippetLanguage::Rust => rust(httpreq),
+        SnippetLanguage::Python => python(httpreq),
+        SnippetLanguage::JavaScript => javascript(httpreq),
+    }
+}
+
+/// Generate the snippets of every request of the template.
+///
+/// The template is rendered with the environment and its secrets are
+/// obfuscated, unless the handlebars variables are kept as placeholders.
+pub fn generate_template(
+    template: &str,
+    environ: &Environment,
+    language: SnippetLanguage,
+    keep_placeholders: bool,
+) -> RustamanResult<String> {
+    let mut snippets = Vec::new();
+    for block in httpparser::split_template(template).iter() {
+        let httpreq = if keep_placeholders {
+            httpparser::parse_template(block.as_str())?
+        } else {
+            httpparser::load_template(block.as_str(), environ)?
+                .obfuscate(environ.obfuscated_string().as_slice())
+        };
+        snippets.push(generate(&httpreq, language));
+    }
+    Ok(snippets.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &str = "#! Authority: localhost:8080
+#! AllowInsecureCertificate
+POST http://example.com/users
+Accept: text/html
+X-Name: O'Brien
+Accept: application/json
+
+{\"name\": \"O'Brien\"}
+";
+
+    fn snippet(template: &str, language: SnippetLanguage) -> String {
+        generate(&httpparser::parse_template(template).unwrap(), language)
+    }
+
+    #[test]
+    fn test_rust() {
+        assert_eq!(
+            snippet(TEMPLATE, SnippetLanguage::Rust),
+            "// Authority: localhost:8080
+let client = reqwest::Client::builder()
+    .danger_accept_invalid_certs(true)
+    .build()?;
+let response = client
+    .request(reqwest::Method::POST, \"http://example.com/users\")
+    .header(\"Accept\", \"text/html\")
+    .header(\"X-Name\", \"O'Brien\")
+    .header(\"Accept\", \"application/json\")
+    .body(\"{\\\"name\\\": \\\"O'Brien\\\"}\")
+    .send()
+    .await?;
+println!(\"{}\", response.text().await?);
+"
+        );
+        assert!(
+            snippet("PROPFIND http://example.com/\n", SnippetLanguage::Rust)
+                .contains(".request(reqwest::Method::from_bytes(b\"PROPFIND\")?, ")
+        );
+    }
+
+    #[test]
+    fn test_python() {
+        assert_eq!(
+            snippet(TEMPLATE, SnippetLanguage::Python),
+            "import requests
+
+# Authority: localhost:8080
+response = requests.request(
+    \"POST\",
+    \"http://example.com/users\",
+    headers={
+        \"Accept\": \"text/html, application/json\",
+        \"X-Name\": \"O'Brien\",
+    },
+    data=\"{\\\"name\\\": \\\"O'Brien\\\"}\",
+    verify=False,
+)
+print(response.text)
+"
+        );
+        assert_eq!(
+            snippet("GET http://example.com/\n", SnippetLanguage::Python),
+            "import requests
+
+response = requests.request(
+    \"GET\",
+    \"http://example.com/\",
+)
+print(response.text)
+"
+        );
+    }
+
+    #[test]
+    fn test_javascript() {
+        assert_eq!(
+            snippet(TEMPLATE, SnippetLanguage::JavaScript),
+            "// Authority: localhost:8080
+// The certificate is not verified, run node with NODE_TLS_REJECT_UNAUTHORIZED=0
+const headers = new Headers();
+headers.append(\"Accept\", \"text/html\");
+headers.append(\"X-Name\", \"O'Brien\");
+headers.append(\"Accept\", \"application/json\");
+const response = await fetch(\"http://example.com/users\", {
+  method: \"POST\",
+  headers,
+  body: \"{\\\"name\\\": \\\"O'Brien\\\"}\",
+});
+console.log(await response.text());
+"
+        );
+    }
+
+    #[test]
+    fn test_generate_template() {
+        let environ = Environment::new(1, "Dev", "token: s3cr3t\n__obfuscated__: [token]\n");
+        let template = "GET http://example.com/\nAuthorization: Bearer {{token}}\n";
+        let snippet =
+            generate_template(template, &environ, SnippetLanguage::Python, false).unwrap();
+        assert!(snippet.contains("\"Authorization\": \"Bearer s3c...\""));
+        let snippet = generate_template(template, &environ, SnippetLanguage::Python, true).unwrap();
+        assert!(snippet.contains("\"Authorization\": \"Bearer {{token}}\""));
+    }
+}
@@ -3,26 +3,117 @@ use std::error::Error;
 use std::fmt::{self, Display};
 use std::io;
 
+use handlebars::RenderErrorReason;
+
+/// The position of an error in the template of a request.
+///
+/// The lines and the columns start at 1, as in the editor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TemplatePosition {
+    /// The index of the request block, as returned by `split_template`.
+    pub block: usize,
+    pub line: usize,
+    pub column: Option<usize>,
+}
+
 #[derive(Debug)]
 pub enum RustamanError {
+    /// An error located in the template of the request.
+    TemplateError(TemplatePosition, Box<RustamanError>),
     RenderError(handlebars::RenderError),
     RequestParsingError(String),
+    CaptureError(String),
+    JsonPathError(String),
+    NotFound(String),
+    AssertionError(String),
+    InvalidArgument(String),
+    ImportError(String),
     EnvironmentParsingError(serde_yaml_ng::Error),
     UrlParseError(url::ParseError),
+    HttpError(reqwest::Error),
     IOError(io::Error),
 }
 
 /// Result used by method that can failed.
 pub type RustamanResult<T> = Result<T, RustamanError>;
 
+impl RustamanError {
+    /// Locate the error on a line of the request block.
+    pub fn at_line(self, line: usize) -> RustamanError {
+        let position = TemplatePosition {
+            block: 0,
+            line,
+            column: None,
+        };
+        RustamanError::TemplateError(position, Box::new(self))
+    }
+
+    /// Locate the error in the template, from its position in the block
+    /// starting at the line `first_line` of the template.
+    ///
+    /// An error without position is returned as is, a wrong line would be marked.
+    pub fn in_block(self, block: usize, first_line: usize) -> RustamanError {
+        let (line, column, error) = match self {
+            RustamanError::TemplateError(position, error) => {
+                (position.line, position.column, *error)
+            }
+            RustamanError::RenderError(err) => {
+                let located = match err.reason() {
+                    RenderErrorReason::TemplateError(tpl_err) => {
+                        tpl_err.pos().map(|(line, column)| (line, Some(column)))
+                    }
+                    _ => err.line_no.map(|line| (line, err.column_no)),
+                };
+                match located {
+                    Some((line, column)) => (line, column, RustamanError::RenderError(err)),
+                    None => return RustamanError::RenderError(err),
+                }
+            }
+            error => return error,
+        };
+        let position = TemplatePosition {
+            block,
+            line: first_line + line.max(1) - 1,
+            column,
+        };
+        RustamanError::TemplateError(position, Box::new(error))
+    }
+
+    /// The position of the error in the template, if it is located.
+    pub fn position(&self) -> Option<TemplatePosition> {
+        match self {
+            RustamanError::TemplateError(position, _) => Some(*position),
+            _ => None,
+        }
+    }
+}
+
 impl Display for RustamanError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
+            RustamanError::TemplateError(position, err) => {
+                match position.column {
+                    Some(column) => write!(f, "Line {}, column {}: ", position.line, column)?,
+                    None => write!(f, "Line {}: ", position.line)?,
+                }
+                match err.as_ref() {
+                    // the position is already displayed.
+                    RustamanError::RenderError(err) => write!(f, "{}", err.reason()),
+                    err => write!(f, "{}", err),
+                }
+            }
             RustamanError::EnvironmentParsingError(err) => {
                 write!(f, "Environment Yaml Parsing Error: {}", err)
             }
             RustamanError::UrlParseError(err) => write!(f, "Url Parse Error: {}", err),
+            RustamanError::HttpError(err) => write!(f, "{}", err),
             RustamanError::RequestParsingError(err) => write!(f, "{}", err),
+            RustamanError::CaptureError(err) => write!(f, "{}", err),
+            RustamanError::JsonPathError(err) => write!(f, "{}", err),
+            RustamanError::NotFound(err) => write!(f, "{}", err),
+            RustamanError::AssertionError(err) => write!(f, "{}", err),
+            RustamanError::InvalidArgument(err) => write!(f, "{}", err),
+            RustamanError::ImportError(err) => write!(f, "{}", err),
             RustamanError::IOError(err) => write!(f, "{}", err),
             RustamanError::RenderError(err) => write!(f, "{}", err),
         }
@@ -32,8 +123,10 @@ impl Display for RustamanError {
 impl Error for RustamanError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         let err: Option<&(dyn Error + 'static)> = match self {
+            RustamanError::TemplateError(_, err) => Some(err.as_ref()),
             RustamanError::EnvironmentParsingError(err) => Some(err),
             RustamanError::UrlParseError(err) => Some(err),
+            RustamanError::HttpError(err) => Some(err),
             RustamanError::IOError(err) => Some(err),
             _ => None,
         };
@@ -53,6 +146,12 @@ impl From<url::ParseError> for RustamanError {
     }
 }
 
+impl From<reqwest::Error> for RustamanError {
+    fn from(err: reqwest::Error) -> RustamanError {
+        RustamanError::HttpError(err)
+    }
+}
+
 impl From<io::Error> for RustamanError {
     fn from(err: io::Error) -> RustamanError {
         RustamanError::IOError(err)
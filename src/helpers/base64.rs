@@ -0,0 +1,103 @@
+//! Base64 of the standard alphabet, for basic auth and the binary bodies.
+const BASE64_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode the data in standard base64, with padding.
+pub fn encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity((data.len() + 2) / 3 * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_CHARS[((n >> (18 - i * 6)) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Decode the base64 encoded data, `None` if it is not valid base64.
+///
+/// The whitespaces are ignored and the padding is optional, but only at the end.
+pub fn decode(encoded: &str) -> Option<Vec<u8>> {
+    let data: Vec<u8> = encoded
+        .bytes()
+        .filter(|chr| !chr.is_ascii_whitespace())
+        .collect();
+    let end = data
+        .iter()
+        .rposition(|chr| *chr != b'=')
+        .map_or(0, |i| i + 1);
+    if data.len() - end > 2 {
+        return None;
+    }
+    let mut decoded = Vec::with_capacity(end / 4 * 3);
+    let mut n: u32 = 0;
+    let mut bits = 0;
+    for chr in data[..end].iter() {
+        let val = BASE64_CHARS.iter().position(|c| c == chr)? as u32;
+        n = (n << 6) | val;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((n >> bits) as u8);
+            n &= (1 << bits) - 1;
+        }
+    }
+    // a single character left does not encode a byte
+    if bits >= 6 {
+        return None;
+    }
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The test vectors of the RFC 4648.
+    const VECTORS: &[(&str, &str)] = &[
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("fooba", "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ];
+
+    #[test]
+    fn test_encode() {
+        for (data, encoded) in VECTORS {
+            assert_eq!(encode(data.as_bytes()), *encoded);
+        }
+        assert_eq!(encode(&[0xfb, 0xff, 0xbf]), "+/+/");
+        assert_eq!(encode(b"user:p@ss"), "dXNlcjpwQHNz");
+    }
+
+    #[test]
+    fn test_decode() {
+        for (data, encoded) in VECTORS {
+            assert_eq!(decode(encoded), Some(data.as_bytes().to_vec()));
+        }
+        assert_eq!(decode("+/+/"), Some(vec![0xfb, 0xff, 0xbf]));
+        // the padding is optional, the whitespaces are ignored
+        assert_eq!(decode("Zm9vYg"), Some(b"foob".to_vec()));
+        assert_eq!(decode("Zm9v\nYmFy\n"), Some(b"foobar".to_vec()));
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert_eq!(decode("Zm9v!"), None);
+        assert_eq!(decode("Zm-_"), None);
+        assert_eq!(decode("Zg==Zg=="), None);
+        assert_eq!(decode("Zg==="), None);
+        assert_eq!(decode("Zm9vY"), None);
+    }
+}
@@ -0,0 +1,230 @@
+//! Capture values from a response in order to render the next requests.
+//!
+//! The directive `#! Capture: token` stores the text of the response body in
+//! the `token` variable, like `#! Capture: token = body`. A selector can be
+//! given to keep only a part of the response:
+//!
+//! ```text
+//! #! Capture: token = $.access_token
+//! #! Capture: location = header:Location
+//! ```
+//!
+//! The captured texts are obfuscated in the traffic log, as the secrets of
+//! the environment.
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value as Json;
+
+use super::http::HttpResponse;
+use super::jsonpath;
+use crate::errors::{RustamanError, RustamanResult};
+
+/// The shorter captured texts, like the ids, are not obfuscated, they would
+/// mask every occurrence of the characters in the requests.
+const MIN_SECRET_LEN: usize = 3;
+
+lazy_static! {
+    pub static ref RE_CAPTURE: Regex =
+        Regex::new(r"^(?P<name>[A-Za-z_][A-Za-z0-9_]*)\s*(=\s*(?P<selector>.+))?$").unwrap();
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selector {
+    /// The body as a string.
+    Body,
+    JsonPath(String),
+    Header(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capture {
+    name: String,
+    selector: Selector,
+}
+
+impl Capture {
+    /// Parse the value of the `#! Capture:` directive.
+    pub fn from_directive(directive: &str) -> RustamanResult<Self> {
+        let cap = RE_CAPTURE.captures(directive.trim()).ok_or_else(|| {
+            RustamanError::RequestParsingError(format!("Invalid capture: {}", directive))
+        })?;
+        let name = cap.name("name").unwrap().as_str().to_string();
+        let selector = match cap.name("selector").map(|s| s.as_str().trim()) {
+            None | Some("body") => Selector::Body,
+            Some(header) if header.to_lowercase().starts_with("header:") => {
+                Selector::Header(header[7..].trim().to_string())
+            }
+            Some(path) => Selector::JsonPath(path.to_string()),
+        };
+        Ok(Capture { name, selector })
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Extract the captured value from the response.
+    pub fn extract(&self, response: &HttpResponse) -> RustamanResult<Json> {
+        let body = response.body();
+        let value = match &self.selector {
+            Selector::Body => Json::String(body.to_string()),
+            Selector::Header(header) => response
+                .header(header.as_str())
+                .map(|hval| Json::String(hval.to_string()))
+                .ok_or_else(|| {
+                    RustamanError::CaptureError(format!(
+                        "Capture {}: header {} not found",
+                        self.name, header
+                    ))
+                })?,
+            Selector::JsonPath(path) => {
+                let doc: Json = serde_json::from_str(body).map_err(|err| {
+                    RustamanError::CaptureError(format!(
+                        "Capture {}: response is not json: {}",
+                        self.name, err
+                    ))
+                })?;
+                let mut selected = jsonpath::select(&doc, path.as_str())?;
+                match selected.len() {
+                    0 => {
+                        return Err(RustamanError::CaptureError(format!(
+                            "Capture {}: nothing matches {}",
+                            self.name, path
+                        )))
+                    }
+                    1 => selected.pop().unwrap().clone(),
+                    _ => Json::Array(selected.into_iter().cloned().collect()),
+                }
+            }
+        };
+        Ok(value)
+    }
+}
+
+/// The captured text to obfuscate in the traffic log, like a token.
+pub fn secret(value: &Json) -> Option<&str> {
+    value
+        .as_str()
+        .filter(|text| text.chars().count() >= MIN_SECRET_LEN)
+}
+
+/// Add the captured value in the context used to render the templates.
+pub fn inject(context: &mut serde_yaml_ng::Value, name: &str, value: Json) -> RustamanResult<()> {
+    let value = serde_yaml_ng::to_value(value)?;
+    if context.is_null() {
+        *context = serde_yaml_ng::Value::Mapping(serde_yaml_ng::Mapping::new());
+    }
+    match context.as_mapping_mut() {
+        Some(mapping) => {
+            mapping.insert(serde_yaml_ng::Value::String(name.to_string()), value);
+            Ok(())
+        }
+        None => Err(RustamanError::CaptureError(format!(
+            "Capture {}: the environment is not a mapping",
+            name
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::HistoryEntry;
+
+    fn response(body: &str) -> HttpResponse {
+        let entry: HistoryEntry = serde_json::from_value(json!({
+            "timestamp": 0,
+            "version": "HTTP/1.1",
+            "status": 200,
+            "reason": "OK",
+            "headers": [["Location", "/users/42"]],
+            "body": body,
+            "duration_ms": 10,
+        }))
+        .unwrap();
+        HttpResponse::from(&entry)
+    }
+
+    fn capture(directive: &str) -> Capture {
+        Capture::from_directive(directive).unwrap()
+    }
+
+    #[test]
+    fn test_from_directive() {
+        assert_eq!(capture("token").name(), "token");
+        assert_eq!(capture("token").selector, Selector::Body);
+        assert_eq!(capture("token = body").selector, Selector::Body);
+        assert_eq!(
+            capture(" token = $.access_token ").selector,
+            Selector::JsonPath("$.access_token".to_string())
+        );
+        assert_eq!(
+            capture("location=header: Location").selector,
+            Selector::Header("Location".to_string())
+        );
+    }
+
+    #[test]
+    fn test_from_directive_error() {
+        assert!(Capture::from_directive("").is_err());
+        assert!(Capture::from_directive("1token").is_err());
+        assert!(Capture::from_directive("the token").is_err());
+        assert!(Capture::from_directive("token =").is_err());
+    }
+
+    #[test]
+    fn test_extract() {
+        let body = r#"{"access_token": "s3cr3t", "user": {"id": 42}, "roles": ["a", "b"]}"#;
+        let response = response(body);
+        assert_eq!(capture("raw").extract(&response).unwrap(), json!(body));
+        assert_eq!(
+            capture("token = $.access_token")
+                .extract(&response)
+                .unwrap(),
+            json!("s3cr3t")
+        );
+        assert_eq!(
+            capture("id = $.user.id").extract(&response).unwrap(),
+            json!(42)
+        );
+        assert_eq!(
+            capture("roles = $.roles[*]").extract(&response).unwrap(),
+            json!(["a", "b"])
+        );
+        assert_eq!(
+            capture("location = header:location")
+                .extract(&response)
+                .unwrap(),
+            json!("/users/42")
+        );
+    }
+
+    #[test]
+    fn test_extract_error() {
+        let not_json = response("not json");
+        assert!(capture("token = $.access_token")
+            .extract(&not_json)
+            .is_err());
+        assert!(capture("etag = header:ETag").extract(&not_json).is_err());
+        let empty = response("{}");
+        assert!(capture("token = $.access_token").extract(&empty).is_err());
+    }
+
+    #[test]
+    fn test_secret() {
+        assert_eq!(secret(&json!("s3cr3t")), Some("s3cr3t"));
+        assert_eq!(secret(&json!("42")), None);
+        assert_eq!(secret(&json!(123456)), None);
+    }
+
+    #[test]
+    fn test_inject() {
+        let mut context = serde_yaml_ng::Value::Null;
+        inject(&mut context, "token", json!("a=b&c")).unwrap();
+        assert_eq!(context["token"].as_str(), Some("a=b&c"));
+        let mut context = serde_yaml_ng::Value::String("scalar".to_string());
+        assert!(inject(&mut context, "token", json!("x")).is_err());
+    }
+}
@@ -0,0 +1,411 @@
+//! Compare two responses side by side, and structurally when both bodies are JSON.
+use std::fmt;
+
+use serde_json::Value as Json;
+
+use super::http::HttpResponse;
+
+/// Above this number of cells, the lines in between the common head and tail
+/// are not aligned anymore, they are all reported as changed.
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowKind {
+    Same,
+    Changed,
+    Removed,
+    Added,
+}
+
+/// A line of the side by side view, a missing side is an empty line.
+#[derive(Debug, Clone)]
+pub struct Row {
+    pub kind: RowKind,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum JsonChange {
+    Added(String, Json),
+    Removed(String, Json),
+    Changed(String, Json, Json),
+}
+
+impl fmt::Display for JsonChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonChange::Added(path, value) => write!(f, "+ {}: {}", path, value),
+            JsonChange::Removed(path, value) => write!(f, "- {}: {}", path, value),
+            JsonChange::Changed(path, left, right) => {
+                write!(f, "~ {}: {} -> {}", path, left, right)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ResponseDiff {
+    pub rows: Vec<Row>,
+    /// Set when both bodies are JSON documents.
+    pub json_changes: Option<Vec<JsonChange>>,
+}
+
+fn parse_json(body: &str) -> Option<Json> {
+    if body.trim().is_empty() {
+        return None;
+    }
+    serde_json::from_str(body).ok()
+}
+
+/// The response as it is compared: the headers are sorted by name,
+/// and a JSON body is prettified, the keys of its objects are sorted by serde_json.
+fn normalize(response: &HttpResponse, json: Option<&Json>) -> String {
+    let mut text = format!(
+        "{} {} {}\n",
+        response.version(),
+        response.status(),
+        response.reason()
+    );
+    let mut headers: Vec<&(String, String)> = response.headers().iter().collect();
+    headers.sort_by_key(|(key, _)| key.to_lowercase());
+    for (key, val) in headers {
+        text.push_str(format!("{}: {}\n", key, val).as_str());
+    }
+    text.push('\n');
+    match json {
+        Some(json) => text.push_str(
+            serde_json::to_string_pretty(json)
+                .unwrap_or_default()
+                .as_str(),
+        ),
+        None => text.push_str(response.body()),
+    }
+    text
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        format!("{}.{}", path, key)
+    } else {
+        format!("{}[{}]", path, Json::String(key.to_string()))
+    }
+}
+
+fn diff_json_at(path: &str, left: &Json, right: &Json, changes: &mut Vec<JsonChange>) {
+    match (left, right) {
+        (Json::Object(lmap), Json::Object(rmap)) => {
+            for (key, lval) in lmap.iter() {
+                let path = child_path(path, key);
+                match rmap.get(key) {
+                    Some(rval) => diff_json_at(path.as_str(), lval, rval, changes),
+                    None => changes.push(JsonChange::Removed(path, lval.clone())),
+                }
+            }
+            for (key, rval) in rmap.iter() {
+                if !lmap.contains_key(key) {
+                    changes.push(JsonChange::Added(child_path(path, key), rval.clone()));
+                }
+            }
+        }
+        (Json::Array(litems), Json::Array(ritems)) => {
+            for (idx, lval) in litems.iter().enumerate() {
+                let path = format!("{}[{}]", path, idx);
+                match ritems.get(idx) {
+                    Some(rval) => diff_json_at(path.as_str(), lval, rval, changes),
+                    None => changes.push(JsonChange::Removed(path, lval.clone())),
+                }
+            }
+            for (idx, rval) in ritems.iter().enumerate().skip(litems.len()) {
+                changes.push(JsonChange::Added(
+                    format!("{}[{}]", path, idx),
+                    rval.clone(),
+                ));
+            }
+        }
+        _ => {
+            if left != right {
+                changes.push(JsonChange::Changed(
+                    path.to_string(),
+                    left.clone(),
+                    right.clone(),
+                ));
+            }
+        }
+    }
+}
+
+/// The structural differences of two JSON documents, by JSONPath.
+pub fn diff_json(left: &Json, right: &Json) -> Vec<JsonChange> {
+    let mut changes = Vec::new();
+    diff_json_at("$", left, right, &mut changes);
+    changes
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Same,
+    Removed,
+    Added,
+}
+
+/// The longest common subsequence of the lines, as edit operations.
+fn lcs_ops(left: &[&str], right: &[&str]) -> Vec<Op> {
+    let (n, m) = (left.len(), right.len());
+    if n * m > MAX_LCS_CELLS {
+        let mut ops = vec![Op::Removed; n];
+        ops.extend(vec![Op::Added; m]);
+        return ops;
+    }
+    // lengths[i][j] is the lcs of left[i..] and right[j..]
+    let mut lengths = vec![0u32; (n + 1) * (m + 1)];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i * (m + 1) + j] = if left[i] == right[j] {
+                lengths[(i + 1) * (m + 1) + j + 1] + 1
+            } else {
+                lengths[(i + 1) * (m + 1) + j].max(lengths[i * (m + 1) + j + 1])
+            };
+        }
+    }
+    let mut ops = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if left[i] == right[j] {
+            ops.push(Op::Same);
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * (m + 1) + j] >= lengths[i * (m + 1) + j + 1] {
+            ops.push(Op::Removed);
+            i += 1;
+        } else {
+            ops.push(Op::Added);
+            j += 1;
+        }
+    }
+    ops.extend(vec![Op::Removed; n - i]);
+    ops.extend(vec![Op::Added; m - j]);
+    ops
+}
+
+/// Align the lines of both texts, side by side.
+pub fn diff_lines(left: &str, right: &str) -> Vec<Row> {
+    let left: Vec<&str> = left.lines().collect();
+    let right: Vec<&str> = right.lines().collect();
+    let head = left
+        .iter()
+        .zip(right.iter())
+        .take_while(|(l, r)| l == r)
+        .count();
+    let tail = left[head..]
+        .iter()
+        .rev()
+        .zip(right[head..].iter().rev())
+        .take_while(|(l, r)| l == r)
+        .count();
+
+    let mut ops = vec![Op::Same; head];
+    ops.extend(lcs_ops(
+        &left[head..left.len() - tail],
+        &right[head..right.len() - tail],
+    ));
+    ops.extend(vec![Op::Same; tail]);
+
+    let mut rows = Vec::with_capacity(ops.len());
+    let (mut i, mut j, mut k) = (0, 0, 0);
+    while k < ops.len() {
+        if ops[k] == Op::Same {
+            rows.push(Row {
+                kind: RowKind::Same,
+                left: Some(left[i].to_string()),
+                right: Some(right[j].to_string()),
+            });
+            i += 1;
+            j += 1;
+            k += 1;
+            continue;
+        }
+        // a hunk of removed and added lines, paired as changed lines.
+        let mut removed = Vec::new();
+        let mut added = Vec::new();
+        while k < ops.len() && ops[k] != Op::Same {
+            if ops[k] == Op::Removed {
+                removed.push(left[i]);
+                i += 1;
+            } else {
+                added.push(right[j]);
+                j += 1;
+            }
+            k += 1;
+        }
+        for idx in 0..removed.len().max(added.len()) {
+            let (lline, rline) = (removed.get(idx), added.get(idx));
+            let kind = match (lline, rline) {
+                (Some(_), Some(_)) => RowKind::Changed,
+                (Some(_), None) => RowKind::Removed,
+                _ => RowKind::Added,
+            };
+            rows.push(Row {
+                kind,
+                left: lline.map(|line| line.to_string()),
+                right: rline.map(|line| line.to_string()),
+            });
+        }
+    }
+    rows
+}
+
+/// Compare two responses, the left one is the reference.
+pub fn diff_responses(left: &HttpResponse, right: &HttpResponse) -> ResponseDiff {
+    let ljson = parse_json(left.body());
+    let rjson = parse_json(right.body());
+    let json_changes = match (ljson.as_ref(), rjson.as_ref()) {
+        (Some(ljson), Some(rjson)) => Some(diff_json(ljson, rjson)),
+        _ => None,
+    };
+    let rows = diff_lines(
+        normalize(left, ljson.as_ref()).as_str(),
+        normalize(right, rjson.as_ref()).as_str(),
+    );
+    ResponseDiff { rows, json_changes }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::HistoryEntry;
+
+    fn response(headers: Json, body: &str) -> HttpResponse {
+        let entry: HistoryEntry = serde_json::from_value(json!({
+            "timestamp": 0,
+            "version": "HTTP/1.1",
+            "status": 200,
+            "reason": "OK",
+            "headers": headers,
+            "body": body,
+            "duration_ms": 10,
+        }))
+        .unwrap();
+        HttpResponse::from(&entry)
+    }
+
+    fn changes(left: Json, right: Json) -> Vec<String> {
+        diff_json(&left, &right)
+            .iter()
+            .map(|change| change.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_json_keys() {
+        assert_eq!(
+            changes(
+                json!({"id": 1, "name": "a", "old": true, "a b": 1}),
+                json!({"id": 1, "name": "b", "new": null, "a b": 2})
+            ),
+            vec![
+                "~ $[\"a b\"]: 1 -> 2",
+                "~ $.name: \"a\" -> \"b\"",
+                "- $.old: true",
+                "+ $.new: null",
+            ]
+        );
+        assert!(changes(json!({"a": [1, {"b": 2}]}), json!({"a": [1, {"b": 2}]})).is_empty());
+    }
+
+    #[test]
+    fn test_diff_json_arrays() {
+        assert_eq!(
+            changes(json!({"items": [1, 2, 3]}), json!({"items": [1, 4]})),
+            vec!["~ $.items[1]: 2 -> 4", "- $.items[2]: 3"]
+        );
+        assert_eq!(
+            changes(json!([{"id": 1}]), json!([{"id": 1}, {"id": 2}])),
+            vec!["+ $[1]: {\"id\":2}"]
+        );
+        assert_eq!(
+            changes(json!({"a": [1]}), json!({"a": {"0": 1}})),
+            vec!["~ $.a: [1] -> {\"0\":1}"]
+        );
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let rows = diff_lines("a\nb\nc\nd", "a\nB\nc\nd\ne");
+        let kinds: Vec<RowKind> = rows.iter().map(|row| row.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                RowKind::Same,
+                RowKind::Changed,
+                RowKind::Same,
+                RowKind::Same,
+                RowKind::Added,
+            ]
+        );
+        assert_eq!(rows[1].left.as_deref(), Some("b"));
+        assert_eq!(rows[1].right.as_deref(), Some("B"));
+        assert_eq!(rows[4].left, None);
+        let rows = diff_lines("a\nb", "a");
+        assert_eq!(rows[1].kind, RowKind::Removed);
+        assert_eq!(rows[1].right, None);
+    }
+
+    #[test]
+    fn test_diff_responses_json() {
+        let left = response(
+            json!([["X-B", "1"], ["Content-Type", "application/json"]]),
+            r#"{"b": 1, "a": 2}"#,
+        );
+        let right = response(
+            json!([["Content-Type", "application/json"], ["X-B", "1"]]),
+            r#"{"a": 2, "b": 3}"#,
+        );
+        let diff = diff_responses(&left, &right);
+        let json_changes: Vec<String> = diff
+            .json_changes
+            .unwrap()
+            .iter()
+            .map(|change| change.to_string())
+            .collect();
+        assert_eq!(json_changes, vec!["~ $.b: 1 -> 3"]);
+        // the headers and the keys are sorted, only the value of b differs
+        let changed: Vec<&Row> = diff
+            .rows
+            .iter()
+            .filter(|row| row.kind != RowKind::Same)
+            .collect();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].left.as_deref(), Some("  \"b\": 1"));
+        assert_eq!(changed[0].right.as_deref(), Some("  \"b\": 3"));
+    }
+
+    #[test]
+    fn test_diff_responses_text() {
+        let left = response(json!([]), r#"{"a":1}"#);
+        let right = response(json!([]), "not json");
+        let diff = diff_responses(&left, &right);
+        assert!(diff.json_changes.is_none());
+        // the JSON side is still prettified, the other one is compared verbatim
+        let changed: Vec<(RowKind, Option<&str>, Option<&str>)> = diff
+            .rows
+            .iter()
+            .filter(|row| row.kind != RowKind::Same)
+            .map(|row| (row.kind, row.left.as_deref(), row.right.as_deref()))
+            .collect();
+        assert_eq!(
+            changed,
+            vec![
+                (RowKind::Changed, Some("{"), Some("not json")),
+                (RowKind::Removed, Some("  \"a\": 1"), None),
+                (RowKind::Removed, Some("}"), None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,304 @@
+//! Assertions on the response, written as directives in the template.
+//!
+//! ```text
+//! #! Expect: status == 200
+//! #! Expect: header Content-Type ~ ^application/json
+//! #! Expect: $.items[0].id == 42
+//! #! Expect: body ~ "created"
+//! #! Expect: duration < 500ms
+//! ```
+//!
+//! The operators are `==`, `!=`, `~` (matches the regex), `!~`, `<`, `<=`,
+//! `>` and `>=`.
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value as Json;
+
+use super::http::HttpResponse;
+use super::jsonpath;
+use crate::errors::{RustamanError, RustamanResult};
+
+lazy_static! {
+    pub static ref RE_EXPECT: Regex = Regex::new(
+        r"^(?P<subject>status|body|duration|header\s+\S+|\$\S*)\s*(?P<operator>==|!=|!~|~|<=|>=|<|>)\s*(?P<value>.*)$"
+    )
+    .unwrap();
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Subject {
+    Status,
+    Header(String),
+    Body,
+    /// Duration in milliseconds
+    Duration,
+    JsonPath(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operator {
+    Equals,
+    NotEquals,
+    Matches,
+    NotMatches,
+    Lower,
+    LowerOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expect {
+    directive: String,
+    subject: Subject,
+    operator: Operator,
+    value: String,
+}
+
+/// The result of an expectation checked against a response.
+#[derive(Debug, Clone)]
+pub struct Assertion {
+    expect: String,
+    failure: Option<String>,
+}
+
+impl Assertion {
+    pub fn expect(&self) -> &str {
+        self.expect.as_str()
+    }
+    pub fn failure(&self) -> Option<&str> {
+        self.failure.as_deref()
+    }
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        &value[1..value.len() - 1]
+    } else {
+        value
+    }
+}
+
+fn as_text(value: &Json) -> String {
+    match value {
+        Json::String(s) => s.clone(),
+        _ => value.to_string(),
+    }
+}
+
+fn as_number(value: &Json) -> Option<f64> {
+    match value {
+        Json::Number(n) => n.as_f64(),
+        Json::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+impl Expect {
+    /// Parse the value of the `#! Expect:` directive.
+    pub fn from_directive(directive: &str) -> RustamanResult<Self> {
+        let directive = directive.trim();
+        let cap = RE_EXPECT.captures(directive).ok_or_else(|| {
+            RustamanError::RequestParsingError(format!("Invalid expectation: {}", directive))
+        })?;
+        let subject = match cap.name("subject").unwrap().as_str() {
+            "status" => Subject::Status,
+            "body" => Subject::Body,
+            "duration" => Subject::Duration,
+            path if path.starts_with('$') => Subject::JsonPath(path.to_string()),
+            header => Subject::Header(header[6..].trim().to_string()),
+        };
+        let operator = match cap.name("operator").unwrap().as_str() {
+            "==" => Operator::Equals,
+            "!=" => Operator::NotEquals,
+            "~" => Operator::Matches,
+            "!~" => Operator::NotMatches,
+            "<" => Operator::Lower,
+            "<=" => Operator::LowerOrEqual,
+            ">" => Operator::Greater,
+            _ => Operator::GreaterOrEqual,
+        };
+        let mut value = cap.name("value").unwrap().as_str().trim().to_string();
+        if subject == Subject::Duration {
+            value = value.trim_end_matches("ms").trim().to_string();
+        }
+        if operator == Operator::Matches || operator == Operator::NotMatches {
+            Regex::new(unquote(value.as_str())).map_err(|err| {
+                RustamanError::RequestParsingError(format!(
+                    "Invalid expectation: {}: {}",
+                    directive, err
+                ))
+            })?;
+        }
+        Ok(Expect {
+            directive: directive.to_string(),
+            subject,
+            operator,
+            value,
+        })
+    }
+
+    fn actual(&self, response: &HttpResponse) -> Result<Option<Json>, String> {
+        let actual = match &self.subject {
+            Subject::Status => Some(Json::from(response.status())),
+            Subject::Header(name) => response
+                .header(name.as_str())
+                .map(|val| Json::String(val.to_string())),
+            Subject::Body => Some(Json::String(response.body().to_string())),
+            Subject::Duration => Some(Json::from(response.elapsed().as_millis() as u64)),
+            Subject::JsonPath(path) => {
+                let doc: Json = serde_json::from_str(response.body())
+                    .map_err(|err| format!("response is not json: {}", err))?;
+                let mut selected =
+                    jsonpath::select(&doc, path.as_str()).map_err(|err| err.to_string())?;
+                match selected.len() {
+                    0 => None,
+                    1 => Some(selected.pop().unwrap().clone()),
+                    _ => Some(Json::Array(selected.into_iter().cloned().collect())),
+                }
+            }
+        };
+        Ok(actual)
+    }
+
+    fn compare(&self, actual: &Json) -> bool {
+        let value = unquote(self.value.as_str());
+        match self.operator {
+            Operator::Equals | Operator::NotEquals => {
+                let expected: Json =
+                    serde_json::from_str(self.value.as_str()).unwrap_or(Json::from(value));
+                let equals = *actual == expected || as_text(actual) == as_text(&expected);
+                equals == (self.operator == Operator::Equals)
+            }
+            Operator::Matches | Operator::NotMatches => {
+                // the regex has been validated while parsing the directive
+                let re = Regex::new(value).unwrap();
+                re.is_match(as_text(actual).as_str()) == (self.operator == Operator::Matches)
+            }
+            _ => match (as_number(actual), value.trim().parse::<f64>().ok()) {
+                (Some(actual), Some(expected)) => match self.operator {
+                    Operator::Lower => actual < expected,
+                    Operator::LowerOrEqual => actual <= expected,
+                    Operator::Greater => actual > expected,
+                    _ => actual >= expected,
+                },
+                _ => false,
+            },
+        }
+    }
+
+    /// Check the expectation against the response.
+    pub fn check(&self, response: &HttpResponse) -> Assertion {
+        let failure = match self.actual(response) {
+            Err(err) => Some(err),
+            Ok(None) => match self.operator {
+                Operator::NotEquals | Operator::NotMatches => None,
+                _ => Some("nothing found".to_string()),
+            },
+            Ok(Some(actual)) => {
+                if self.compare(&actual) {
+                    None
+                } else {
+                    let mut actual = as_text(&actual);
+                    if actual.len() > 80 {
+                        let mut end = 80;
+                        while !actual.is_char_boundary(end) {
+                            end -= 1;
+                        }
+                        actual.truncate(end);
+                        actual.push_str("...");
+                    }
+                    Some(format!("got {}", actual))
+                }
+            }
+        };
+        Assertion {
+            expect: self.directive.clone(),
+            failure,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::HistoryEntry;
+
+    fn response(body: &str) -> HttpResponse {
+        let entry: HistoryEntry = serde_json::from_value(json!({
+            "timestamp": 0,
+            "version": "HTTP/1.1",
+            "status": 201,
+            "reason": "Created",
+            "headers": [["Content-Type", "application/json"]],
+            "body": body,
+            "duration_ms": 120,
+        }))
+        .unwrap();
+        HttpResponse::from(&entry)
+    }
+
+    fn check(directive: &str, body: &str) -> Assertion {
+        Expect::from_directive(directive)
+            .unwrap()
+            .check(&response(body))
+    }
+
+    #[test]
+    fn test_from_directive() {
+        let expect = Expect::from_directive("header Content-Type ~ ^application/json").unwrap();
+        assert_eq!(expect.subject, Subject::Header("Content-Type".to_string()));
+        assert_eq!(expect.operator, Operator::Matches);
+        assert_eq!(expect.value, "^application/json");
+
+        let expect = Expect::from_directive("duration < 500ms").unwrap();
+        assert_eq!(expect.subject, Subject::Duration);
+        assert_eq!(expect.operator, Operator::Lower);
+        assert_eq!(expect.value, "500");
+
+        let expect = Expect::from_directive("$.items[0].id >= 42").unwrap();
+        assert_eq!(
+            expect.subject,
+            Subject::JsonPath("$.items[0].id".to_string())
+        );
+        assert_eq!(expect.operator, Operator::GreaterOrEqual);
+    }
+
+    #[test]
+    fn test_from_directive_error() {
+        assert!(Expect::from_directive("status is 200").is_err());
+        assert!(Expect::from_directive("body ~ (unclosed").is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let body = r#"{"items": [{"id": 42, "name": "created"}]}"#;
+        assert!(check("status == 201", body).passed());
+        assert!(check("header content-type ~ ^application/json", body).passed());
+        assert!(check("header X-Missing != 1", body).passed());
+        assert!(check("$.items[0].id == 42", body).passed());
+        assert!(check("$.items[0].name == \"created\"", body).passed());
+        assert!(check("body ~ \"created\"", body).passed());
+        assert!(check("duration < 500ms", body).passed());
+    }
+
+    #[test]
+    fn test_check_failure() {
+        let body = r#"{"items": []}"#;
+        assert_eq!(check("status == 200", body).failure(), Some("got 201"));
+        assert_eq!(
+            check("$.items[0].id == 42", body).failure(),
+            Some("nothing found")
+        );
+        assert!(check("$.items == 1", "not json")
+            .failure()
+            .unwrap()
+            .starts_with("response is not json"));
+        assert!(!check("duration > 1000", body).passed());
+    }
+}
@@ -0,0 +1,415 @@
+//! Format the response bodies for display, by media type.
+use url::form_urlencoded;
+
+/// Elements of HTML that never have a closing tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Elements of HTML that contain text that must not be reindented.
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "pre", "textarea"];
+
+struct Formatter {
+    /// The sourceview language used to highlight the formatted body.
+    language: Option<&'static str>,
+    matches: fn(&str) -> bool,
+    format: fn(&str) -> Option<String>,
+}
+
+const FORMATTERS: &[Formatter] = &[
+    Formatter {
+        language: Some("json"),
+        matches: is_json,
+        format: format_json,
+    },
+    Formatter {
+        language: Some("html"),
+        matches: is_html,
+        format: format_html,
+    },
+    Formatter {
+        language: Some("xml"),
+        matches: is_xml,
+        format: format_xml,
+    },
+    Formatter {
+        language: Some("yaml"),
+        matches: is_yaml,
+        format: format_verbatim,
+    },
+    Formatter {
+        language: Some("ini"),
+        matches: is_form,
+        format: format_form,
+    },
+];
+
+/// The media type without its parameters, in lowercase.
+///
+/// `application/problem+json; charset=utf-8` is `application/problem+json`.
+pub fn essence(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_lowercase()
+}
+
+/// The value of a parameter of the media type, like the charset.
+pub fn parameter(content_type: &str, name: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        if key.trim().eq_ignore_ascii_case(name) {
+            Some(value.trim().trim_matches('"').to_string())
+        } else {
+            None
+        }
+    })
+}
+
+fn has_suffix(essence: &str, suffix: &str) -> bool {
+    essence.ends_with(format!("+{}", suffix).as_str())
+}
+
+fn is_json(essence: &str) -> bool {
+    essence == "application/json" || essence == "text/json" || has_suffix(essence, "json")
+}
+
+fn is_html(essence: &str) -> bool {
+    essence == "text/html" || essence == "application/xhtml+xml"
+}
+
+fn is_xml(essence: &str) -> bool {
+    essence == "application/xml" || essence == "text/xml" || has_suffix(essence, "xml")
+}
+
+fn is_yaml(essence: &str) -> bool {
+    matches!(
+        essence,
+        "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml"
+    ) || has_suffix(essence, "yaml")
+}
+
+fn is_form(essence: &str) -> bool {
+    essence == "application/x-www-form-urlencoded"
+}
+
+fn format_json(body: &str) -> Option<String> {
+    let obj: serde_json::Value = serde_json::from_str(body).ok()?;
+    serde_json::to_string_pretty(&obj).ok()
+}
+
+fn format_verbatim(body: &str) -> Option<String> {
+    Some(body.to_string())
+}
+
+/// Display the form fields as a table of decoded `name = value` lines.
+fn format_form(body: &str) -> Option<String> {
+    let fields: Vec<(String, String)> = form_urlencoded::parse(body.trim().as_bytes())
+        .map(|(key, val)| (key.to_string(), val.to_string()))
+        .collect();
+    let width = fields
+        .iter()
+        .map(|(key, _)| key.chars().count())
+        .max()
+        .unwrap_or(0);
+    Some(
+        fields
+            .iter()
+            .map(|(key, val)| format!("{:width$} = {}", key, val, width = width))
+            .collect::<Vec<String>>()
+            .join("\n"),
+    )
+}
+
+fn format_xml(body: &str) -> Option<String> {
+    indent_markup(body, false)
+}
+
+fn format_html(body: &str) -> Option<String> {
+    indent_markup(body, true)
+}
+
+#[derive(Debug)]
+enum Token<'a> {
+    /// Comments, doctype, processing instructions and CDATA, kept as is.
+    Verbatim(&'a str),
+    Open(&'a str, String, bool),
+    Close(&'a str),
+    Text(&'a str),
+}
+
+/// The end of the tag starting at `start`, the quoted `>` are skipped.
+fn tag_end(markup: &str, start: usize) -> Option<usize> {
+    let mut quote = None;
+    for (idx, chr) in markup[start..].char_indices() {
+        match (quote, chr) {
+            (None, '"') | (None, '\'') => quote = Some(chr),
+            (Some(q), _) if q == chr => quote = None,
+            (None, '>') => return Some(start + idx + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches("</")
+        .trim_start_matches('<')
+        .split(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .next()
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+fn tokenize(markup: &str, html: bool) -> Option<Vec<Token<'_>>> {
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < markup.len() {
+        let rest = &markup[pos..];
+        if !rest.starts_with('<') {
+            let end = rest.find('<').map(|idx| pos + idx).unwrap_or(markup.len());
+            tokens.push(Token::Text(&markup[pos..end]));
+            pos = end;
+            continue;
+        }
+        let delimited = [("<!--", "-->"), ("<![CDATA[", "]]>"), ("<?", "?>")]
+            .iter()
+            .find(|(open, _)| rest.starts_with(open));
+        let end = match delimited {
+            Some((_, close)) => pos + rest.find(close)? + close.len(),
+            None => tag_end(markup, pos)?,
+        };
+        let tag = &markup[pos..end];
+        if delimited.is_some() || tag.starts_with("<!") {
+            tokens.push(Token::Verbatim(tag));
+        } else if tag.starts_with("</") {
+            tokens.push(Token::Close(tag));
+        } else {
+            let name = tag_name(tag);
+            let self_closing =
+                tag.ends_with("/>") || (html && VOID_ELEMENTS.contains(&name.as_str()));
+            let raw_text = html && !self_closing && RAW_TEXT_ELEMENTS.contains(&name.as_str());
+            tokens.push(Token::Open(tag, name.clone(), self_closing));
+            if raw_text {
+                // the content is kept up to the closing tag.
+                let closing = format!("</{}", name);
+                let content_end = markup[end..]
+                    .to_ascii_lowercase()
+                    .find(closing.as_str())
+                    .map(|idx| end + idx)
+                    .unwrap_or(markup.len());
+                if content_end > end {
+                    tokens.push(Token::Verbatim(&markup[end..content_end]));
+                }
+                pos = content_end;
+                continue;
+            }
+        }
+        pos = end;
+    }
+    Some(tokens)
+}
+
+/// Reindent XML or HTML, one tag per line, except the tags that contain text only.
+fn indent_markup(markup: &str, html: bool) -> Option<String> {
+    if !markup.trim_start().starts_with('<') {
+        return None;
+    }
+    let tokens = tokenize(markup.trim(), html)?;
+    let mut lines: Vec<String> = Vec::new();
+    let mut depth: usize = 0;
+    let mut idx = 0;
+    while idx < tokens.len() {
+        let indent = "  ".repeat(depth);
+        match &tokens[idx] {
+            Token::Open(tag, name, self_closing) => {
+                if *self_closing {
+                    lines.push(format!("{}{}", indent, tag));
+                } else if let (Some(Token::Verbatim(text)), Some(Token::Close(close))) =
+                    (tokens.get(idx + 1), tokens.get(idx + 2))
+                {
+                    // the content of a raw text element is not reindented.
+                    lines.push(format!("{}{}{}{}", indent, tag, text, close));
+                    idx += 3;
+                    continue;
+                } else if let (Some(Token::Text(text)), Some(Token::Close(close))) =
+                    (tokens.get(idx + 1), tokens.get(idx + 2))
+                {
+                    if tag_name(close) == *name {
+                        lines.push(format!("{}{}{}{}", indent, tag, text.trim(), close));
+                        idx += 3;
+                        continue;
+                    }
+                    lines.push(format!("{}{}", indent, tag));
+                    depth += 1;
+                } else if let Some(Token::Close(close)) = tokens.get(idx + 1) {
+                    lines.push(format!("{}{}{}", indent, tag, close));
+                    idx += 2;
+                    continue;
+                } else {
+                    lines.push(format!("{}{}", indent, tag));
+                    depth += 1;
+                }
+            }
+            Token::Close(tag) => {
+                depth = depth.saturating_sub(1);
+                lines.push(format!("{}{}", "  ".repeat(depth), tag));
+            }
+            Token::Text(text) => {
+                let text = text.trim();
+                if !text.is_empty() {
+                    lines.push(format!("{}{}", indent, text));
+                }
+            }
+            Token::Verbatim(text) => {
+                if !text.trim().is_empty() {
+                    lines.push(format!("{}{}", indent, text.trim_matches('\n')));
+                }
+            }
+        }
+        idx += 1;
+    }
+    Some(lines.join("\n"))
+}
+
+/// Format the body for the content type of the response.
+///
+/// Return the formatted body and the sourceview language to highlight it,
+/// the body is returned unchanged if it cannot be formatted.
+pub fn format(content_type: Option<&str>, body: &str) -> (String, Option<&'static str>) {
+    let essence = essence(content_type.unwrap_or(""));
+    match FORMATTERS
+        .iter()
+        .find(|formatter| (formatter.matches)(essence.as_str()))
+    {
+        Some(formatter) => match (formatter.format)(body) {
+            Some(formatted) => (formatted, formatter.language),
+            None => (body.to_string(), formatter.language),
+        },
+        None => (body.to_string(), None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_essence_and_parameter() {
+        let content_type = "Application/Problem+JSON; charset=\"UTF-8\"; q=1";
+        assert_eq!(essence(content_type), "application/problem+json");
+        assert_eq!(
+            parameter(content_type, "Charset"),
+            Some("UTF-8".to_string())
+        );
+        assert_eq!(parameter(content_type, "boundary"), None);
+        assert_eq!(essence(""), "");
+    }
+
+    #[test]
+    fn test_format_json() {
+        assert_eq!(
+            format(Some("application/vnd.api+json"), r#"{"a":1,"b":[true]}"#),
+            (
+                "{\n  \"a\": 1,\n  \"b\": [\n    true\n  ]\n}".to_string(),
+                Some("json")
+            )
+        );
+        assert_eq!(
+            format(Some("application/json"), "{\"a\":"),
+            ("{\"a\":".to_string(), Some("json"))
+        );
+    }
+
+    #[test]
+    fn test_format_html() {
+        let body = "<!DOCTYPE html><html><head><title>T</title><meta charset=\"utf-8\"></head>\
+                    <body><p>Hello <b>you</b></p><br><script>if (a < b) {}</script></body></html>";
+        let expected = [
+            "<!DOCTYPE html>",
+            "<html>",
+            "  <head>",
+            "    <title>T</title>",
+            "    <meta charset=\"utf-8\">",
+            "  </head>",
+            "  <body>",
+            "    <p>",
+            "      Hello",
+            "      <b>you</b>",
+            "    </p>",
+            "    <br>",
+            "    <script>if (a < b) {}</script>",
+            "  </body>",
+            "</html>",
+        ];
+        assert_eq!(
+            format(Some("text/html; charset=utf-8"), body),
+            (expected.join("\n"), Some("html"))
+        );
+    }
+
+    #[test]
+    fn test_format_xml() {
+        let body = "<?xml version=\"1.0\"?><a><b x=\"1>2\">text</b><c/><!-- note -->\
+                    <d><![CDATA[<raw>]]></d><e></e></a>";
+        let expected = [
+            "<?xml version=\"1.0\"?>",
+            "<a>",
+            "  <b x=\"1>2\">text</b>",
+            "  <c/>",
+            "  <!-- note -->",
+            "  <d><![CDATA[<raw>]]></d>",
+            "  <e></e>",
+            "</a>",
+        ];
+        assert_eq!(
+            format(Some("application/atom+xml"), body),
+            (expected.join("\n"), Some("xml"))
+        );
+    }
+
+    #[test]
+    fn test_format_malformed_markup() {
+        for body in ["<a><b", "<a><!-- unclosed", "not markup"] {
+            assert_eq!(
+                format(Some("text/xml"), body),
+                (body.to_string(), Some("xml"))
+            );
+        }
+        assert_eq!(
+            format(Some("text/html"), "<p x=\"unclosed>"),
+            ("<p x=\"unclosed>".to_string(), Some("html"))
+        );
+    }
+
+    #[test]
+    fn test_format_yaml() {
+        assert_eq!(
+            format(Some("application/x-yaml"), "a:   1\n"),
+            ("a:   1\n".to_string(), Some("yaml"))
+        );
+    }
+
+    #[test]
+    fn test_format_form() {
+        assert_eq!(
+            format(
+                Some("application/x-www-form-urlencoded"),
+                "a=1&long+name=x%20y\n"
+            ),
+            ("a         = 1\nlong name = x y".to_string(), Some("ini"))
+        );
+    }
+
+    #[test]
+    fn test_format_unknown() {
+        assert_eq!(
+            format(Some("text/plain"), "  text "),
+            ("  text ".to_string(), None)
+        );
+        assert_eq!(format(None, "{}"), ("{}".to_string(), None));
+    }
+}
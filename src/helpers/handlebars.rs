@@ -2,11 +2,12 @@ use std::boxed::Box;
 
 use handlebars::{
     Context, Decorator, Handlebars, Helper, HelperResult, Output, RenderContext, RenderError,
-    RenderErrorReason,
+    RenderErrorReason, Renderable,
 };
 use serde_json::value::Value as Json;
 use url::form_urlencoded;
 
+use super::base64;
 use crate::errors::RustamanResult;
 
 // a decorator mutates current context data
@@ -51,10 +52,63 @@ fn encode(
     Ok(())
 }
 
+// encode the parameter, or the rendered block, `{{#base64}}{{user}}:{{password}}{{/base64}}`
+fn base64<'reg, 'rc>(
+    h: &Helper<'rc>,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    rc: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let content = match h.template() {
+        Some(t) => t.renders(r, ctx, rc)?,
+        None => h
+            .param(0)
+            .and_then(|v| v.value().as_str())
+            .unwrap_or("")
+            .to_string(),
+    };
+    out.write(base64::encode(content.as_bytes()).as_str())?;
+    Ok(())
+}
+
+/// Render the template, the values are written verbatim, the requests are not HTML.
 pub fn render_template(template: &str, context: &serde_yaml_ng::Value) -> RustamanResult<String> {
     let mut hbar = Handlebars::new();
+    hbar.register_escape_fn(handlebars::no_escape);
     hbar.register_decorator("set", Box::new(set_decorator));
     hbar.register_helper("encode", Box::new(encode));
+    hbar.register_helper("base64", Box::new(base64));
     let resp = hbar.render_template(template, &context)?;
     Ok(resp)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> serde_yaml_ng::Value {
+        serde_yaml_ng::from_str("token: \"a=b&c='d' <e> `f`\"\nuser: john\npassword: p&ss=\n")
+            .unwrap()
+    }
+
+    #[test]
+    fn test_render_template_does_not_escape() {
+        assert_eq!(
+            render_template("Authorization: Bearer {{token}}", &context()).unwrap(),
+            "Authorization: Bearer a=b&c='d' <e> `f`"
+        );
+    }
+
+    #[test]
+    fn test_render_template_helpers() {
+        assert_eq!(
+            render_template("{{#base64}}{{user}}:{{password}}{{/base64}}", &context()).unwrap(),
+            base64::encode(b"john:p&ss=")
+        );
+        assert_eq!(
+            render_template("q={{encode token}}", &context()).unwrap(),
+            "q=a%3Db%26c%3D%27d%27+%3Ce%3E+%60f%60"
+        );
+    }
+}
@@ -0,0 +1,704 @@
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::header::HOST;
+use reqwest::Version;
+use tokio::net::{lookup_host, TcpStream};
+use tower::{Layer, Service};
+use url::Url;
+
+use super::formatter;
+use super::httpparser::HttpRequest;
+use crate::errors::RustamanResult;
+use crate::models::{HistoryEntry, USER_AGENT};
+
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    version: String,
+    status: u16,
+    reason: String,
+    headers: Vec<(String, String)>,
+    body: String,
+    raw_body: Vec<u8>,
+    binary: bool,
+    elapsed: Duration,
+    timing: Option<Timing>,
+}
+
+/// Width of the bars of the waterfall.
+const WATERFALL_WIDTH: usize = 40;
+
+/// The phases of the request, the response of the history have no timing.
+#[derive(Debug, Clone, Default)]
+pub struct Timing {
+    pub dns: Duration,
+    /// The TCP connection.
+    pub connect: Duration,
+    /// The TLS handshake, none in clear text.
+    pub tls: Option<Duration>,
+    /// From the request sent to the first byte of the response.
+    pub wait: Duration,
+    pub download: Duration,
+}
+
+impl Timing {
+    pub fn total(&self) -> Duration {
+        self.dns + self.connect + self.tls.unwrap_or_default() + self.wait + self.download
+    }
+
+    pub fn phases(&self) -> Vec<(&'static str, Duration)> {
+        let mut phases = vec![("DNS lookup", self.dns), ("TCP connect", self.connect)];
+        if let Some(tls) = self.tls {
+            phases.push(("TLS handshake", tls));
+        }
+        phases.push(("Waiting (TTFB)", self.wait));
+        phases.push(("Download", self.download));
+        phases
+    }
+
+    /// Display the phases as a waterfall, one line per phase.
+    pub fn waterfall(&self) -> String {
+        let total = self.total().as_secs_f64();
+        let mut lines = Vec::new();
+        let mut offset = 0.0;
+        for (name, duration) in self.phases() {
+            let (start, width) = if total > 0.0 {
+                let start = (offset / total * WATERFALL_WIDTH as f64).round() as usize;
+                let end = ((offset + duration.as_secs_f64()) / total * WATERFALL_WIDTH as f64)
+                    .round() as usize;
+                (start, end.saturating_sub(start))
+            } else {
+                (0, 0)
+            };
+            offset += duration.as_secs_f64();
+            lines.push(format!(
+                "{:<18} {:>9.3} ms |{}{}{}|",
+                name,
+                duration.as_secs_f64() * 1000.0,
+                " ".repeat(start),
+                "=".repeat(width),
+                " ".repeat(WATERFALL_WIDTH.saturating_sub(start + width))
+            ));
+        }
+        lines.push(format!(
+            "{:<18} {:>9.3} ms",
+            "Total",
+            self.total().as_secs_f64() * 1000.0
+        ));
+        lines.join("\n")
+    }
+}
+
+/// Media types of documents that are never read as text.
+const BINARY_MEDIA_TYPES: &[&str] = &[
+    "image/",
+    "audio/",
+    "video/",
+    "font/",
+    "application/octet-stream",
+    "application/pdf",
+    "application/zip",
+    "application/gzip",
+    "application/wasm",
+    "application/protobuf",
+    "application/x-protobuf",
+];
+
+/// Media types of images that can be previewed.
+const IMAGE_MEDIA_TYPES: &[&str] = &[
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "image/bmp",
+    "image/svg+xml",
+];
+
+fn is_text_media_type(essence: &str) -> bool {
+    essence.starts_with("text/")
+        || essence.ends_with("+json")
+        || essence.ends_with("+xml")
+        || essence.ends_with("+yaml")
+        || matches!(
+            essence,
+            "application/json"
+                | "application/xml"
+                | "application/yaml"
+                | "application/x-yaml"
+                | "application/javascript"
+                | "application/ecmascript"
+                | "application/graphql"
+                | "application/x-www-form-urlencoded"
+        )
+}
+
+/// Decode the body with the charset of the content type, UTF-8 by default.
+///
+/// Return `None` for a binary body, detected by its media type, even with
+/// a charset, or by its content when the media type is unknown.
+pub fn decode_body(content_type: Option<&str>, bytes: &[u8]) -> Option<String> {
+    let content_type = content_type.unwrap_or("");
+    let essence = formatter::essence(content_type);
+    let is_text = is_text_media_type(essence.as_str());
+    if !is_text
+        && BINARY_MEDIA_TYPES
+            .iter()
+            .any(|media_type| essence.starts_with(media_type))
+    {
+        return None;
+    }
+    if let Some(charset) = formatter::parameter(content_type, "charset") {
+        if let Some(encoding) = encoding_rs::Encoding::for_label(charset.as_bytes()) {
+            let (text, _, _) = encoding.decode(bytes);
+            return Some(text.to_string());
+        }
+        warn!("Unknown charset {}, decoding the body as UTF-8", charset);
+    }
+    if is_text {
+        return Some(String::from_utf8_lossy(bytes).to_string());
+    }
+    if bytes.contains(&0) {
+        return None;
+    }
+    std::str::from_utf8(bytes).ok().map(|text| text.to_string())
+}
+
+impl HttpResponse {
+    pub fn version(&self) -> &str {
+        self.version.as_str()
+    }
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+    pub fn reason(&self) -> &str {
+        self.reason.as_str()
+    }
+    pub fn headers(&self) -> &[(String, String)] {
+        self.headers.as_slice()
+    }
+    /// Value of the first header matching the name, case insensitive.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, val)| val.as_str())
+    }
+    /// The decoded body, empty for a binary body.
+    pub fn body(&self) -> &str {
+        self.body.as_str()
+    }
+    /// The body as it has been received.
+    pub fn raw_body(&self) -> &[u8] {
+        self.raw_body.as_slice()
+    }
+    pub fn is_binary(&self) -> bool {
+        self.binary
+    }
+    /// Tell if the body is an image that can be previewed.
+    pub fn is_image(&self) -> bool {
+        let essence = formatter::essence(self.header("content-type").unwrap_or(""));
+        IMAGE_MEDIA_TYPES.contains(&essence.as_str())
+    }
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+    pub fn timing(&self) -> Option<&Timing> {
+        self.timing.as_ref()
+    }
+
+    /// The response as it is displayed in the traffic log.
+    pub fn http_frame(&self) -> String {
+        let mut resp = String::new();
+        resp.push_str(self.version());
+        resp.push(' ');
+        resp.push_str(self.status.to_string().as_str());
+        resp.push(' ');
+        resp.push_str(self.reason());
+        resp.push_str("\r\n");
+        for (key, val) in self.headers() {
+            resp.push_str(key.as_str());
+            resp.push_str(": ");
+            resp.push_str(val.as_str());
+            resp.push_str("\r\n");
+        }
+        resp.push_str("\r\n");
+        if self.binary {
+            resp.push_str(format!("<{} bytes of binary data>", self.raw_body.len()).as_str());
+        } else {
+            resp.push_str(self.body());
+        }
+        resp
+    }
+}
+
+impl From<&HistoryEntry> for HttpResponse {
+    fn from(entry: &HistoryEntry) -> Self {
+        HttpResponse {
+            version: entry.version().to_string(),
+            status: entry.status(),
+            reason: entry.reason().to_string(),
+            headers: entry.headers().to_vec(),
+            body: entry.body().to_string(),
+            raw_body: entry.raw_body(),
+            binary: entry.is_binary(),
+            elapsed: entry.duration(),
+            timing: None,
+        }
+    }
+}
+
+/// Resolve the authority of the `#! Authority:` directive, like the curl `--connect-to`.
+///
+/// Return the domain to override, its addresses, and the url to query, that
+/// contains the port of the authority since the port of the url wins over
+/// the resolved one.
+async fn connect_to(
+    url: &str,
+    authority: &(String, u16),
+) -> RustamanResult<(String, Vec<SocketAddr>, String)> {
+    let (host, port) = authority;
+    let addrs: Vec<SocketAddr> = lookup_host((host.as_str(), *port)).await?.collect();
+    let mut url = Url::parse(url)?;
+    if url.domain().is_none() {
+        // ip addresses are not resolved, so there is nothing to override.
+        let _ = url.set_host(Some(host.as_str()));
+    }
+    let domain = url.host_str().unwrap_or_default().to_string();
+    let _ = url.set_port(Some(*port));
+    Ok((domain, addrs, url.to_string()))
+}
+
+/// The Host header of the url of the template, the url sent may target
+/// another authority.
+fn host_header(url: &str) -> Option<String> {
+    let parsed_url = Url::parse(url).ok()?;
+    let host = parsed_url.host_str().unwrap_or("No host found");
+    Some(match parsed_url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    })
+}
+
+/// The durations measured while the http client connects.
+#[derive(Debug, Clone, Default)]
+struct Probe {
+    dns: Arc<Mutex<Option<Duration>>>,
+    tcp: Arc<Mutex<Option<Duration>>>,
+    connect: Arc<Mutex<Option<Duration>>>,
+    /// The port of the https url, its TCP connection is timed apart.
+    tls_port: Option<u16>,
+}
+
+impl Probe {
+    fn dns(&self) -> Option<Duration> {
+        *self.dns.lock().unwrap()
+    }
+    fn tcp(&self) -> Option<Duration> {
+        *self.tcp.lock().unwrap()
+    }
+    fn connect(&self) -> Option<Duration> {
+        *self.connect.lock().unwrap()
+    }
+}
+
+/// Time a TCP connection to the first address.
+///
+/// The http client connects and negotiates TLS at once, the TCP connection
+/// is timed on its own connection to split the TLS handshake out.
+async fn time_tcp_connect(addrs: &[SocketAddr]) -> Option<Duration> {
+    let addr = addrs.first()?;
+    let start = Instant::now();
+    TcpStream::connect(addr).await.ok()?;
+    Some(start.elapsed())
+}
+
+/// Resolve the names, as the http client does, measuring the lookup.
+struct TimedResolver(Probe);
+
+impl Resolve for TimedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let probe = self.0.clone();
+        Box::pin(async move {
+            let start = Instant::now();
+            let addrs: Vec<SocketAddr> = lookup_host((name.as_str(), 0)).await?.collect();
+            *probe.dns.lock().unwrap() = Some(start.elapsed());
+            if let Some(port) = probe.tls_port {
+                let targets: Vec<SocketAddr> = addrs
+                    .iter()
+                    .map(|addr| SocketAddr::new(addr.ip(), port))
+                    .collect();
+                *probe.tcp.lock().unwrap() = time_tcp_connect(targets.as_slice()).await;
+            }
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// Measure the connection of the http client, the resolution of the name,
+/// the TCP connection timed by the resolver and the TLS handshake are included.
+#[derive(Clone)]
+struct TimedConnect(Probe);
+
+impl<S> Layer<S> for TimedConnect {
+    type Service = TimedConnectService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TimedConnectService {
+            inner,
+            probe: self.0.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct TimedConnectService<S> {
+    inner: S,
+    probe: Probe,
+}
+
+impl<S, R> Service<R> for TimedConnectService<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let connect = self.probe.connect.clone();
+        let start = Instant::now();
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            let response = future.await;
+            *connect.lock().unwrap() = Some(start.elapsed());
+            response
+        })
+    }
+}
+
+/// Send the request and read the whole response.
+pub async fn send(httpreq: &HttpRequest) -> RustamanResult<HttpResponse> {
+    let time = Instant::now();
+    let mut probe = Probe::default();
+    if let Ok(parsed_url) = Url::parse(httpreq.url()) {
+        if parsed_url.scheme() == "https" {
+            probe.tls_port = parsed_url.port_or_known_default();
+        }
+    }
+    let mut cbuilder = reqwest::ClientBuilder::new()
+        .user_agent(USER_AGENT)
+        .dns_resolver(Arc::new(TimedResolver(probe.clone())))
+        .connector_layer(TimedConnect(probe.clone()));
+    if !httpreq.verify_cert() {
+        cbuilder = cbuilder.danger_accept_invalid_certs(true);
+    }
+    // the version of the request line is forced, HTTP/2 is sent without
+    // negotiation, so it also works in clear text (h2c).
+    cbuilder = match httpreq.version() {
+        Some(Version::HTTP_10) | Some(Version::HTTP_11) => cbuilder.http1_only(),
+        Some(Version::HTTP_2) => cbuilder.http2_prior_knowledge(),
+        _ => cbuilder,
+    };
+    let mut url = httpreq.url().to_string();
+    // the authority is resolved here, not by the http client.
+    let mut authority_dns = Duration::ZERO;
+    let mut authority_tcp = None;
+    if let Some(authority) = httpreq.authority() {
+        let (domain, addrs, connect_url) = connect_to(httpreq.url(), authority).await?;
+        debug!("Connecting {} to {:?}", domain, addrs);
+        cbuilder = cbuilder.resolve_to_addrs(domain.as_str(), &addrs);
+        url = connect_url;
+        authority_dns = time.elapsed();
+        // the resolver is not called for the overridden domain.
+        if probe.tls_port.is_some() {
+            authority_tcp = time_tcp_connect(addrs.as_slice()).await;
+        }
+    }
+    let cli = cbuilder.build()?;
+    let mut req = cli.request(httpreq.method(), url.as_str());
+    if let Some(version) = httpreq.version() {
+        req = req.version(version);
+    }
+    let mut has_host: bool = false;
+    for (key, val) in httpreq.headers() {
+        has_host = has_host || key.to_lowercase() == "host";
+        req = req.header(key, val);
+    }
+
+    if !has_host {
+        if let Some(host) = host_header(httpreq.url()) {
+            req = req.header(HOST, host);
+        }
+    }
+
+    if let Some(body) = httpreq.body() {
+        req = req.body(body.to_string());
+    }
+
+    let sent = Instant::now();
+    let response = req.send().await?;
+    let first_byte = sent.elapsed();
+    let version = format!("{:?}", response.version());
+    let status = response.status();
+    let headers = response
+        .headers()
+        .iter()
+        .map(|(key, hval)| {
+            (
+                key.to_string(),
+                String::from_utf8_lossy(hval.as_bytes()).to_string(),
+            )
+        })
+        .collect::<Vec<(String, String)>>();
+    let download = Instant::now();
+    let raw_body = response.bytes().await?.to_vec();
+    let resolved = probe.dns().unwrap_or_default();
+    let connected = probe.connect().unwrap_or_default();
+    // the TCP connection timed by the resolver is not part of the handshake.
+    let handshake = connected
+        .saturating_sub(resolved)
+        .saturating_sub(probe.tcp().unwrap_or_default());
+    let (connect, tls) = match (probe.tls_port, authority_tcp.or(probe.tcp())) {
+        (Some(_), Some(tcp)) => (tcp.min(handshake), Some(handshake.saturating_sub(tcp))),
+        (Some(_), None) => (handshake, Some(Duration::ZERO)),
+        (None, _) => (handshake, None),
+    };
+    let timing = Timing {
+        dns: authority_dns + resolved,
+        connect,
+        tls,
+        wait: first_byte.saturating_sub(connected),
+        download: download.elapsed(),
+    };
+    let elapsed = time.elapsed();
+    let content_type = headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-type"))
+        .map(|(_, val)| val.as_str());
+    let body = decode_body(content_type, raw_body.as_slice());
+    let binary = body.is_none();
+
+    Ok(HttpResponse {
+        version,
+        status: status.as_u16(),
+        reason: status.canonical_reason().unwrap_or("").to_string(),
+        headers,
+        body: body.unwrap_or_default(),
+        raw_body,
+        binary,
+        elapsed,
+        timing: Some(timing),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing(tls: Option<Duration>) -> Timing {
+        Timing {
+            dns: Duration::from_millis(10),
+            connect: Duration::from_millis(20),
+            tls,
+            wait: Duration::from_millis(40),
+            download: Duration::from_millis(10),
+        }
+    }
+
+    fn connect(url: &str, host: &str, port: u16) -> (String, Vec<SocketAddr>, String) {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(connect_to(url, &(host.to_string(), port)))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_connect_to() {
+        // the domain of the url is the SNI, the port of the authority is sent.
+        let url = "https://example.com/users?page=1";
+        let (domain, addrs, connect_url) = connect(url, "127.0.0.1", 8443);
+        assert_eq!(domain, "example.com");
+        assert_eq!(addrs, vec!["127.0.0.1:8443".parse().unwrap()]);
+        assert_eq!(connect_url, "https://example.com:8443/users?page=1");
+        assert_eq!(host_header(url), Some("example.com".to_string()));
+
+        let url = "http://example.com:8080/";
+        let (domain, addrs, connect_url) = connect(url, "127.0.0.1", 9000);
+        assert_eq!(domain, "example.com");
+        assert_eq!(addrs, vec!["127.0.0.1:9000".parse().unwrap()]);
+        assert_eq!(connect_url, "http://example.com:9000/");
+        assert_eq!(host_header(url), Some("example.com:8080".to_string()));
+    }
+
+    #[test]
+    fn test_connect_to_default_port() {
+        // the default port of the scheme is not written in the url.
+        let url = "https://example.com:443/";
+        let (domain, _, connect_url) = connect(url, "127.0.0.1", 443);
+        assert_eq!(domain, "example.com");
+        assert_eq!(connect_url, "https://example.com/");
+        assert_eq!(host_header(url), Some("example.com".to_string()));
+
+        let (_, _, connect_url) = connect("http://example.com/", "127.0.0.1", 443);
+        assert_eq!(connect_url, "http://example.com:443/");
+    }
+
+    #[test]
+    fn test_connect_to_ip() {
+        // an ip address is replaced by the host of the authority.
+        let url = "http://10.0.0.1:8080/health";
+        let (domain, addrs, connect_url) = connect(url, "127.0.0.1", 9000);
+        assert_eq!(domain, "127.0.0.1");
+        assert_eq!(addrs, vec!["127.0.0.1:9000".parse().unwrap()]);
+        assert_eq!(connect_url, "http://127.0.0.1:9000/health");
+        assert_eq!(host_header(url), Some("10.0.0.1:8080".to_string()));
+    }
+
+    #[test]
+    fn test_decode_body_charset() {
+        assert_eq!(
+            decode_body(Some("text/plain; charset=ISO-8859-1"), b"caf\xe9"),
+            Some("café".to_string())
+        );
+        assert_eq!(
+            decode_body(Some("text/plain; charset=latin1"), b"caf\xe9"),
+            Some("café".to_string())
+        );
+        assert_eq!(
+            decode_body(Some("text/plain; charset=Shift_JIS"), b"\x82\xa0"),
+            Some("あ".to_string())
+        );
+        assert_eq!(
+            decode_body(Some("application/json; charset=utf-8"), "\"é\"".as_bytes()),
+            Some("\"é\"".to_string())
+        );
+        // an unknown charset falls back to UTF-8.
+        assert_eq!(
+            decode_body(Some("text/plain; charset=klingon"), b"caf\xe9"),
+            Some("caf\u{FFFD}".to_string())
+        );
+    }
+
+    #[test]
+    fn test_decode_body_media_type() {
+        assert_eq!(
+            decode_body(Some("text/html"), b"caf\xe9"),
+            Some("caf\u{FFFD}".to_string())
+        );
+        assert_eq!(
+            decode_body(Some("image/svg+xml"), b"<svg/>"),
+            Some("<svg/>".to_string())
+        );
+        assert_eq!(decode_body(Some("image/png"), b"\x89PNG"), None);
+        assert_eq!(decode_body(Some("image/png; charset=utf-8"), b"PNG"), None);
+        assert_eq!(
+            decode_body(Some("application/octet-stream; charset=utf-8"), b"text"),
+            None
+        );
+        assert_eq!(decode_body(Some("application/pdf"), b"%PDF-1.4"), None);
+    }
+
+    #[test]
+    fn test_decode_body_content() {
+        assert_eq!(
+            decode_body(None, "héllo".as_bytes()),
+            Some("héllo".to_string())
+        );
+        assert_eq!(
+            decode_body(Some("application/x-custom"), b"plain"),
+            Some("plain".to_string())
+        );
+        assert_eq!(decode_body(None, b"a\x00b"), None);
+        assert_eq!(decode_body(None, b"caf\xe9"), None);
+        assert_eq!(
+            decode_body(Some("text/plain"), b"a\x00b"),
+            Some("a\u{0}b".to_string())
+        );
+    }
+
+    #[test]
+    fn test_timing_total() {
+        assert_eq!(timing(None).total(), Duration::from_millis(80));
+        assert_eq!(
+            timing(Some(Duration::from_millis(20))).total(),
+            Duration::from_millis(100)
+        );
+    }
+
+    #[test]
+    fn test_timing_phases() {
+        let names = |timing: Timing| -> Vec<&'static str> {
+            timing.phases().iter().map(|(name, _)| *name).collect()
+        };
+        assert_eq!(
+            names(timing(None)),
+            vec!["DNS lookup", "TCP connect", "Waiting (TTFB)", "Download"]
+        );
+        assert_eq!(
+            timing(Some(Duration::from_millis(20))).phases(),
+            vec![
+                ("DNS lookup", Duration::from_millis(10)),
+                ("TCP connect", Duration::from_millis(20)),
+                ("TLS handshake", Duration::from_millis(20)),
+                ("Waiting (TTFB)", Duration::from_millis(40)),
+                ("Download", Duration::from_millis(10)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_timing_waterfall() {
+        let waterfall = timing(Some(Duration::from_millis(20))).waterfall();
+        let lines: Vec<&str> = waterfall.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                format!(
+                    "DNS lookup            10.000 ms |{}{}|",
+                    "=".repeat(4),
+                    " ".repeat(36)
+                ),
+                format!(
+                    "TCP connect           20.000 ms |{}{}{}|",
+                    " ".repeat(4),
+                    "=".repeat(8),
+                    " ".repeat(28)
+                ),
+                format!(
+                    "TLS handshake         20.000 ms |{}{}{}|",
+                    " ".repeat(12),
+                    "=".repeat(8),
+                    " ".repeat(20)
+                ),
+                format!(
+                    "Waiting (TTFB)        40.000 ms |{}{}{}|",
+                    " ".repeat(20),
+                    "=".repeat(16),
+                    " ".repeat(4)
+                ),
+                format!(
+                    "Download              10.000 ms |{}{}|",
+                    " ".repeat(36),
+                    "=".repeat(4)
+                ),
+                "Total                100.000 ms".to_string(),
+            ]
+        );
+        assert!(Timing::default()
+            .waterfall()
+            .contains(&format!("|{}|", " ".repeat(40))));
+    }
+}
@@ -1,4 +1,3 @@
-use std::collections::HashMap;
 use std::convert::From;
 use std::str::FromStr;
 
@@ -6,9 +5,11 @@ use lazy_static::lazy_static;
 
 use super::super::errors::{RustamanError, RustamanResult};
 use super::super::models::Environment;
+use super::capture::Capture;
+use super::expect::Expect;
 use super::handlebars;
 use regex::Regex;
-use reqwest::Method;
+use reqwest::{Method, Version};
 
 lazy_static! {
     pub static ref RE_EXTRACT_AUTHORITY_FROM_DIRECTIVE: Regex =
@@ -19,6 +20,8 @@ lazy_static! {
     pub static ref RE_EXTRACT_CAPTURE: Regex =
         Regex::new(r"#![\s]*Capture:\s*(?P<capture>.+)").unwrap();
     pub static ref RE_SPLIT_END_CAPTURE: Regex = Regex::new(r"#![\s]*EndCapture").unwrap();
+    pub static ref RE_EXTRACT_EXPECT: Regex =
+        Regex::new(r"#![\s]*Expect:\s*(?P<expect>.+)").unwrap();
 }
 
 fn extract_authority_from_directive(line: &str) -> Option<(String, u16)> {
@@ -40,6 +43,34 @@ fn extract_insecure_flag(line: &str) -> bool {
     RE_EXTRACT_INSECURE_FLAG.is_match(line)
 }
 
+fn extract_capture(line: &str) -> Option<RustamanResult<Capture>> {
+    RE_EXTRACT_CAPTURE.captures(line).and_then(|cap| {
+        cap.name("capture")
+            .map(|capture| Capture::from_directive(capture.as_str()))
+    })
+}
+
+/// The version of the request line, `None` to let the client negotiate it.
+fn parse_version(version: Option<&str>) -> RustamanResult<Option<Version>> {
+    match version {
+        None => Ok(None),
+        Some("HTTP/1.0") => Ok(Some(Version::HTTP_10)),
+        Some("HTTP/1.1") => Ok(Some(Version::HTTP_11)),
+        Some("HTTP/2") | Some("HTTP/2.0") => Ok(Some(Version::HTTP_2)),
+        Some(version) => Err(RustamanError::RequestParsingError(format!(
+            "Unsupported HTTP version {}, expected HTTP/1.0, HTTP/1.1 or HTTP/2",
+            version
+        ))),
+    }
+}
+
+fn extract_expect(line: &str) -> Option<RustamanResult<Expect>> {
+    RE_EXTRACT_EXPECT.captures(line).and_then(|cap| {
+        cap.name("expect")
+            .map(|expect| Expect::from_directive(expect.as_str()))
+    })
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Scheme {
     HTTP,
@@ -62,12 +93,17 @@ pub struct HttpRequest {
     // pub scheme: Scheme,
     pub method: Method,
     pub url: String,
+    /// The version written on the request line, `None` if it is negotiated.
+    pub version: Option<Version>,
     pub body: Option<String>,
-    pub headers: HashMap<String, String>,
-    // host: String,
-    // port: u16,
+    /// The headers in the order of the template, a name may be repeated.
+    pub headers: Vec<(String, String)>,
+    /// Connect to this host and port instead of the one in the url.
+    pub authority: Option<(String, u16)>,
     pub http_frame: String,
     pub verify_cert: bool,
+    pub captures: Vec<Capture>,
+    pub expects: Vec<Expect>,
 }
 
 impl HttpRequest {
@@ -80,8 +116,14 @@ impl HttpRequest {
     pub fn url(&self) -> &str {
         self.url.as_str()
     }
-    pub fn headers(&self) -> &HashMap<String, String> {
-        &self.headers
+    pub fn version(&self) -> Option<Version> {
+        self.version
+    }
+    pub fn headers(&self) -> &[(String, String)] {
+        self.headers.as_slice()
+    }
+    pub fn authority(&self) -> Option<&(String, u16)> {
+        self.authority.as_ref()
     }
     pub fn body(&self) -> Option<String> {
         match &self.body {
@@ -93,15 +135,30 @@ impl HttpRequest {
     pub fn http_frame(&self) -> &str {
         self.http_frame.as_str()
     }
-    /// Obfusface the http_frame
-    pub fn obfuscate(&self, env: &Environment) -> HttpRequest {
+    pub fn captures(&self) -> &[Capture] {
+        self.captures.as_slice()
+    }
+    pub fn expects(&self) -> &[Expect] {
+        self.expects.as_slice()
+    }
+    /// Obfuscate the secrets, of the environment and the captured ones
+    pub fn obfuscate(&self, secrets: &[String]) -> HttpRequest {
         let mut req = self.clone();
-        let s = env.obfuscated_string();
-        let _: Vec<_> = s
+        let _: Vec<_> = secrets
             .iter()
+            // an empty secret would be replaced everywhere.
+            .filter(|x| !x.is_empty())
             .map(|x| {
-                let obf = format!("{}...", &x[0..3]);
+                let obf = format!("{}...", x.chars().take(3).collect::<String>());
                 req.http_frame = req.http_frame.replace(x.as_str(), obf.as_str());
+                req.url = req.url.replace(x.as_str(), obf.as_str());
+                for (_, val) in req.headers.iter_mut() {
+                    *val = val.replace(x.as_str(), obf.as_str());
+                }
+                req.body = req
+                    .body
+                    .as_ref()
+                    .map(|b| b.replace(x.as_str(), obf.as_str()));
             })
             .collect();
         req
@@ -111,24 +168,31 @@ impl HttpRequest {
 fn parse_request(request: &str) -> RustamanResult<HttpRequest> {
     info!("Parsing request {}", request.len());
 
-    let mut lines = request.lines();
+    // the line numbers start at 1, as in the editor.
+    let mut lines = request.lines().zip(1..);
     let mut line = lines.next();
-    // let mut authority: Option<(String, u16)> = None;
+    let mut authority: Option<(String, u16)> = None;
     let mut verify_cert = true;
+    let mut captures = Vec::new();
+    let mut expects = Vec::new();
 
     loop {
         if line.is_none() {
             break;
         }
-        let unwrapped = line.unwrap();
+        let (unwrapped, lineno) = line.unwrap();
         if !unwrapped.is_empty() && !unwrapped.starts_with('#') {
             break;
         }
         if let Some(auth) = extract_authority_from_directive(unwrapped) {
             debug!("Authority found from the request comment: {:?}", auth);
-            // authority = Some(auth);
+            authority = Some(auth);
         } else if extract_insecure_flag(unwrapped) {
             verify_cert = false;
+        } else if let Some(capture) = extract_capture(unwrapped) {
+            captures.push(capture.map_err(|err| err.at_line(lineno))?);
+        } else if let Some(expect) = extract_expect(unwrapped) {
+            expects.push(expect.map_err(|err| err.at_line(lineno))?);
         } else {
             debug!("Ignoring comment {}", unwrapped);
         }
@@ -142,44 +206,73 @@ fn parse_request(request: &str) -> RustamanResult<HttpRequest> {
     }
 
     info!("Parsing First line {:?}", line);
-    let verb_url_version: Vec<&str> = RE_SPLIT_HTTP_FIRST_LINE.split(line.unwrap()).collect();
+    let (line, lineno) = line.unwrap();
+    let verb_url_version: Vec<&str> = RE_SPLIT_HTTP_FIRST_LINE.split(line).collect();
     let (verb, url, version) = match verb_url_version.len() {
-        2 => (verb_url_version[0], verb_url_version[1], "HTTP/1.1"),
+        2 => (verb_url_version[0], verb_url_version[1], None),
         3 => (
             verb_url_version[0],
             verb_url_version[1],
-            verb_url_version[2],
+            Some(verb_url_version[2]),
         ),
         _ => {
-            error!("Parse error on line: {}", line.unwrap());
+            error!("Parse error on line {}: {}", lineno, line);
             return Err(RustamanError::RequestParsingError(format!(
-                "Parse error on line: {}",
-                line.unwrap()
-            )));
+                "Invalid request line: {}",
+                line
+            ))
+            .at_line(lineno));
         }
     };
-    let method = Method::from_str(verb).unwrap();
+    // any token is a method, like the WebDAV PROPFIND or the cache PURGE.
+    let method = Method::from_str(verb).map_err(|_| {
+        RustamanError::RequestParsingError(format!(
+            "Invalid method {}, a method is a token, like GET or PROPFIND",
+            verb
+        ))
+        .at_line(lineno)
+    })?;
+    let http_version = parse_version(version).map_err(|err| err.at_line(lineno))?;
 
-    let mut http_frame = line.unwrap().to_string();
-    http_frame.push_str(" ");
-    http_frame.push_str(version);
-    http_frame.push_str("\r\n");
-    let mut headers = HashMap::new();
+    let mut http_frame = format!("{} {} {}\r\n", verb, url, version.unwrap_or("HTTP/1.1"));
+    let mut headers: Vec<(String, String)> = Vec::new();
     loop {
         let line = lines.next();
         match line {
-            Some(unwrapped) => {
-                if unwrapped.is_empty() {
+            Some((unwrapped, lineno)) => {
+                // a blank line separates the headers from the body, even with spaces.
+                if unwrapped.trim().is_empty() {
                     break;
                 }
-                let header = unwrapped.split_once(':');
-                if let Some((key, val)) = header {
-                    headers.insert(key.to_string(), val.to_string());
-                    http_frame.push_str(unwrapped);
-                    http_frame.push_str("\r\n");
-                } else {
-                    // FIXME: multiline header
-                    break;
+                if unwrapped.starts_with(' ') || unwrapped.starts_with('\t') {
+                    // a folded header, the line continues the value of the previous one.
+                    match headers.last_mut() {
+                        Some((_, val)) => {
+                            if !val.is_empty() {
+                                val.push(' ');
+                            }
+                            val.push_str(unwrapped.trim());
+                            continue;
+                        }
+                        None => {
+                            return Err(RustamanError::RequestParsingError(
+                                "Continuation line without header".to_string(),
+                            )
+                            .at_line(lineno));
+                        }
+                    }
+                }
+                match unwrapped.split_once(':') {
+                    Some((key, val)) if !key.trim().is_empty() => {
+                        headers.push((key.trim().to_string(), val.trim().to_string()));
+                    }
+                    _ => {
+                        return Err(RustamanError::RequestParsingError(format!(
+                            "Invalid header {}, an empty line is expected before the body",
+                            unwrapped
+                        ))
+                        .at_line(lineno));
+                    }
                 }
             }
             None => {
@@ -187,13 +280,17 @@ fn parse_request(request: &str) -> RustamanResult<HttpRequest> {
             }
         }
     }
+    // the frame displays the headers as they are sent.
+    for (key, val) in headers.iter() {
+        http_frame.push_str(format!("{}: {}\r\n", key, val).as_str());
+    }
     http_frame.push_str("\r\n");
 
     let mut body = String::new();
     loop {
         let line = lines.next();
         match line {
-            Some(unwrapped) => {
+            Some((unwrapped, _)) => {
                 body.push_str(unwrapped);
                 body.push_str("\r\n");
 
@@ -209,26 +306,169 @@ fn parse_request(request: &str) -> RustamanResult<HttpRequest> {
         // scheme,
         method,
         url: url.to_string(),
+        version: http_version,
         headers,
         body: if body.is_empty() { None } else { Some(body) },
-        // host,
-        // port,
+        authority,
         http_frame,
         verify_cert,
+        captures,
+        expects,
     })
 }
 
-pub fn split_template(template: &str) -> Vec<String> {
-    let requests: Vec<String> = RE_SPLIT_END_CAPTURE
-        .split(template)
-        .map(|request| request.to_string())
-        .collect();
+/// Split the template in requests, with the line of the template where each one starts.
+pub fn split_template_lines(template: &str) -> Vec<(usize, String)> {
+    let mut requests = Vec::new();
+    let mut start = 0;
+    let separators = RE_SPLIT_END_CAPTURE
+        .find_iter(template)
+        .map(|separator| (separator.start(), separator.end()))
+        .chain(std::iter::once((template.len(), template.len())));
+    for (end, next) in separators {
+        let request = &template[start..end];
+        if !request.trim().is_empty() {
+            let line = template[..start].matches('\n').count() + 1;
+            requests.push((line, request.to_string()));
+        }
+        start = next;
+    }
     debug!("{:?}", requests);
     requests
 }
 
+pub fn split_template(template: &str) -> Vec<String> {
+    split_template_lines(template)
+        .into_iter()
+        .map(|(_, request)| request)
+        .collect()
+}
+
 pub fn load_template(template: &str, environ: &Environment) -> RustamanResult<HttpRequest> {
     let context = environ.parsed_payload()?;
-    let template_rendered = handlebars::render_template(template, &context)?;
+    load_template_with_context(template, &context)
+}
+
+/// Parse the template without rendering it, the handlebars expressions are kept.
+pub fn parse_template(template: &str) -> RustamanResult<HttpRequest> {
+    parse_request(template)
+}
+
+/// Load the template using a context that contains the captured values.
+pub fn load_template_with_context(
+    template: &str,
+    context: &serde_yaml_ng::Value,
+) -> RustamanResult<HttpRequest> {
+    let template_rendered = handlebars::render_template(template, context)?;
     parse_request(template_rendered.as_str())
+        .map_err(|err| locate_in_template(err, template, template_rendered.as_str()))
+}
+
+/// The parse errors are located in the rendered request, the line is searched in the
+/// template. If the rendering altered it, the error refers to the rendered request
+/// and it has no position in the template.
+fn locate_in_template(err: RustamanError, template: &str, rendered: &str) -> RustamanError {
+    let (line, error) = match err {
+        RustamanError::TemplateError(position, error) => (position.line, error),
+        err => return err,
+    };
+    let text = rendered.lines().nth(line - 1).unwrap_or("");
+    let mut found = template
+        .lines()
+        .zip(1..)
+        .filter(|(template_line, _)| *template_line == text);
+    match (found.next(), found.next()) {
+        (Some((_, lineno)), None) => (*error).at_line(lineno),
+        _ => RustamanError::RequestParsingError(format!(
+            "Line {} of the rendered request: {}",
+            line, error
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeated_headers() {
+        let httpreq = parse_template(
+            "POST http://example.com/\n\
+             Accept: text/html\n\
+             Set-Cookie: a=1\n\
+             Accept: application/json\n\
+             \n\
+             body\n",
+        )
+        .unwrap();
+        assert_eq!(
+            httpreq.headers(),
+            &[
+                ("Accept".to_string(), "text/html".to_string()),
+                ("Set-Cookie".to_string(), "a=1".to_string()),
+                ("Accept".to_string(), "application/json".to_string()),
+            ]
+        );
+        assert!(httpreq.http_frame().starts_with(
+            "POST http://example.com/ HTTP/1.1\r\n\
+             Accept: text/html\r\n\
+             Set-Cookie: a=1\r\n\
+             Accept: application/json\r\n\
+             \r\n"
+        ));
+    }
+
+    #[test]
+    fn test_folded_headers() {
+        let httpreq = parse_template(
+            "GET http://example.com/\n\
+             X-Long: first\n  second\n\tthird\n\
+             Accept: */*\n",
+        )
+        .unwrap();
+        assert_eq!(
+            httpreq.headers(),
+            &[
+                ("X-Long".to_string(), "first second third".to_string()),
+                ("Accept".to_string(), "*/*".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_blank_line_with_spaces_ends_the_headers() {
+        let httpreq =
+            parse_template("POST http://example.com/\nAccept: */*\n   \nkey: value\n").unwrap();
+        assert_eq!(
+            httpreq.headers(),
+            &[("Accept".to_string(), "*/*".to_string())]
+        );
+        assert_eq!(httpreq.body(), Some("key: value\r\n".to_string()));
+    }
+
+    #[test]
+    fn test_folded_header_error() {
+        let err = parse_template("GET http://example.com/\n  orphan\n").unwrap_err();
+        assert_eq!(err.position().map(|position| position.line), Some(2));
+        let err = parse_template("GET http://example.com/\nnot a header\n").unwrap_err();
+        assert_eq!(err.position().map(|position| position.line), Some(2));
+    }
+
+    #[test]
+    fn test_templated_header_error() {
+        let context: serde_yaml_ng::Value =
+            serde_yaml_ng::from_str("url: http://example.com/\nheader: not a header\n").unwrap();
+        let err = load_template_with_context("GET {{url}}\nnot a header\n", &context)
+            .unwrap_err()
+            .in_block(1, 5);
+        assert_eq!(err.position().map(|position| position.line), Some(6));
+        // the line rendered from the template is not searched in it
+        let err = load_template_with_context("GET {{url}}\n{{header}}\n", &context)
+            .unwrap_err()
+            .in_block(1, 5);
+        assert!(err.position().is_none());
+        assert!(err
+            .to_string()
+            .starts_with("Line 2 of the rendered request"));
+    }
 }
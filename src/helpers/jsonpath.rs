@@ -0,0 +1,296 @@
+//! A small subset of JSONPath used to select values from json responses.
+//!
+//! Supported syntax: `$`, `.key`, `['key']`, `[0]`, `[-1]`, `[*]`, `.*`
+//! and the recursive descent `..key`.
+use std::iter::Peekable;
+use std::str::Chars;
+
+use serde_json::Value;
+
+use crate::errors::{RustamanError, RustamanResult};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Child(String),
+    Index(i64),
+    Wildcard,
+    Descendant(String),
+    DescendantWildcard,
+}
+
+fn parse_error(path: &str, reason: &str) -> RustamanError {
+    RustamanError::JsonPathError(format!("Invalid path {}: {}", path, reason))
+}
+
+fn parse_name(chars: &mut Peekable<Chars>) -> String {
+    let mut name = String::new();
+    while let Some(&c) = chars.peek() {
+        if c == '.' || c == '[' {
+            break;
+        }
+        name.push(c);
+        chars.next();
+    }
+    name
+}
+
+fn parse_bracket(path: &str, chars: &mut Peekable<Chars>) -> RustamanResult<Segment> {
+    let mut content = String::new();
+    let mut quote: Option<char> = None;
+    loop {
+        match chars.next() {
+            None => return Err(parse_error(path, "missing ]")),
+            Some(c) if Some(c) == quote => {
+                quote = None;
+                content.push(c);
+            }
+            Some(c) if quote.is_none() && (c == '\'' || c == '"') => {
+                quote = Some(c);
+                content.push(c);
+            }
+            Some(']') if quote.is_none() => break,
+            Some(c) => content.push(c),
+        }
+    }
+    let content = content.trim();
+    if content.is_empty() || content == "*" {
+        return Ok(Segment::Wildcard);
+    }
+    if content.len() >= 2
+        && (content.starts_with('\'') && content.ends_with('\'')
+            || content.starts_with('"') && content.ends_with('"'))
+    {
+        return Ok(Segment::Child(content[1..content.len() - 1].to_string()));
+    }
+    content
+        .parse::<i64>()
+        .map(Segment::Index)
+        .map_err(|_| parse_error(path, format!("unexpected [{}]", content).as_str()))
+}
+
+fn parse(path: &str) -> RustamanResult<Vec<Segment>> {
+    let path = path.trim();
+    let mut chars = path.chars().peekable();
+    let mut segments = Vec::new();
+    match chars.peek() {
+        Some('$') => {
+            chars.next();
+        }
+        Some('.') | Some('[') | None => {}
+        // a bare name, like `data.items`
+        Some(_) => segments.push(Segment::Child(parse_name(&mut chars))),
+    }
+    loop {
+        match chars.next() {
+            None => break,
+            Some('.') => {
+                let recursive = chars.peek() == Some(&'.');
+                if recursive {
+                    chars.next();
+                }
+                match chars.peek() {
+                    Some('*') => {
+                        chars.next();
+                        segments.push(if recursive {
+                            Segment::DescendantWildcard
+                        } else {
+                            Segment::Wildcard
+                        });
+                    }
+                    Some('[') => {
+                        chars.next();
+                        let segment = parse_bracket(path, &mut chars)?;
+                        segments.push(match (recursive, segment) {
+                            (true, Segment::Child(name)) => Segment::Descendant(name),
+                            (true, Segment::Wildcard) => Segment::DescendantWildcard,
+                            (true, _) => return Err(parse_error(path, "unexpected index")),
+                            (false, segment) => segment,
+                        });
+                    }
+                    // jq style identity, `.`
+                    None if !recursive && segments.is_empty() => {}
+                    _ => {
+                        let name = parse_name(&mut chars);
+                        if name.is_empty() {
+                            return Err(parse_error(path, "missing key"));
+                        }
+                        segments.push(if recursive {
+                            Segment::Descendant(name)
+                        } else {
+                            Segment::Child(name)
+                        });
+                    }
+                }
+            }
+            Some('[') => segments.push(parse_bracket(path, &mut chars)?),
+            Some(c) => return Err(parse_error(path, format!("unexpected {}", c).as_str())),
+        }
+    }
+    Ok(segments)
+}
+
+fn descendants<'a>(node: &'a Value, nodes: &mut Vec<&'a Value>) {
+    match node {
+        Value::Object(map) => {
+            for child in map.values() {
+                nodes.push(child);
+                descendants(child, nodes);
+            }
+        }
+        Value::Array(arr) => {
+            for child in arr.iter() {
+                nodes.push(child);
+                descendants(child, nodes);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl Segment {
+    fn apply<'a>(&self, node: &'a Value, selected: &mut Vec<&'a Value>) {
+        match self {
+            Segment::Child(name) => {
+                if let Some(child) = node.get(name.as_str()) {
+                    selected.push(child);
+                }
+            }
+            Segment::Index(idx) => {
+                if let Value::Array(arr) = node {
+                    let idx = if *idx < 0 {
+                        arr.len() as i64 + idx
+                    } else {
+                        *idx
+                    };
+                    if idx >= 0 {
+                        if let Some(child) = arr.get(idx as usize) {
+                            selected.push(child);
+                        }
+                    }
+                }
+            }
+            Segment::Wildcard => match node {
+                Value::Object(map) => selected.extend(map.values()),
+                Value::Array(arr) => selected.extend(arr.iter()),
+                _ => {}
+            },
+            Segment::Descendant(name) => {
+                let mut nodes = vec![node];
+                descendants(node, &mut nodes);
+                for node in nodes {
+                    if let Some(child) = node.get(name.as_str()) {
+                        selected.push(child);
+                    }
+                }
+            }
+            Segment::DescendantWildcard => descendants(node, selected),
+        }
+    }
+}
+
+/// Return every values of the document matching the path.
+pub fn select<'a>(value: &'a Value, path: &str) -> RustamanResult<Vec<&'a Value>> {
+    let segments = parse(path)?;
+    let mut current = vec![value];
+    for segment in segments.iter() {
+        let mut selected = Vec::new();
+        for node in current {
+            segment.apply(node, &mut selected);
+        }
+        current = selected;
+    }
+    Ok(current)
+}
+
+/// Keep the part of the JSON document matching the path, prettified.
+///
+/// A single match is returned as is, many matches are returned as an array.
+pub fn filter(document: &str, path: &str) -> RustamanResult<String> {
+    let value: Value = serde_json::from_str(document)
+        .map_err(|_| RustamanError::JsonPathError("The body is not a JSON document".to_string()))?;
+    let selected = select(&value, path)?;
+    let filtered = match selected.as_slice() {
+        [single] => (*single).clone(),
+        _ => Value::Array(selected.into_iter().cloned().collect()),
+    };
+    Ok(serde_json::to_string_pretty(&filtered).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn document() -> Value {
+        json!({
+            "data": {
+                "items": [
+                    {"id": 1, "name": "a"},
+                    {"id": 2, "name": "b"},
+                ],
+                "total": 2,
+            }
+        })
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("$.data['items'][-1]").unwrap(),
+            vec![
+                Segment::Child("data".to_string()),
+                Segment::Child("items".to_string()),
+                Segment::Index(-1),
+            ]
+        );
+        assert_eq!(
+            parse("data..id").unwrap(),
+            vec![
+                Segment::Child("data".to_string()),
+                Segment::Descendant("id".to_string()),
+            ]
+        );
+        assert_eq!(parse(".").unwrap(), vec![]);
+        assert_eq!(parse("$..*").unwrap(), vec![Segment::DescendantWildcard]);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(parse("$.data[0").is_err());
+        assert!(parse("$.data[x]").is_err());
+        assert!(parse("$.data.").is_err());
+        assert!(parse("$..[0]").is_err());
+    }
+
+    #[test]
+    fn test_select() {
+        let doc = document();
+        assert_eq!(select(&doc, "$.data.total").unwrap(), vec![&json!(2)]);
+        assert_eq!(
+            select(&doc, "$.data.items[-1].name").unwrap(),
+            vec![&json!("b")]
+        );
+        assert_eq!(
+            select(&doc, "$.data.items[*].id").unwrap(),
+            vec![&json!(1), &json!(2)]
+        );
+        assert_eq!(
+            select(&doc, "$..name").unwrap(),
+            vec![&json!("a"), &json!("b")]
+        );
+        assert!(select(&doc, "$.data.items[5]").unwrap().is_empty());
+        assert!(select(&doc, "$.missing").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_filter() {
+        let doc = document().to_string();
+        assert_eq!(filter(doc.as_str(), "$.data.total").unwrap(), "2");
+        assert_eq!(
+            filter(doc.as_str(), "$.data.items[*].id").unwrap(),
+            "[\n  1,\n  2\n]"
+        );
+        assert!(filter("not json", "$").is_err());
+    }
+}
@@ -1,4 +1,13 @@
+pub(crate) mod base64;
+pub(crate) mod capture;
+pub(crate) mod diff;
+pub(crate) mod expect;
+pub(crate) mod formatter;
 pub(crate) mod handlebars;
+pub(crate) mod http;
 pub(crate) mod httpparser;
+pub(crate) mod jsonpath;
 pub(crate) mod path;
+pub(crate) mod report;
+pub(crate) mod runner;
 pub(crate) mod sourceview;
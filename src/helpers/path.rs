@@ -45,8 +45,17 @@ pub fn workspace(filename: &str) -> io::Result<PathBuf> {
 }
 
 pub fn write_file(filepath: &str, filecontent: &str) -> io::Result<()> {
+    write_file_with_mode(filepath, filecontent, 0o644)
+}
+
+/// Write a file readable by its owner only, for the responses that may contain secrets.
+pub fn write_private_file(filepath: &str, filecontent: &str) -> io::Result<()> {
+    write_file_with_mode(filepath, filecontent, 0o600)
+}
+
+fn write_file_with_mode(filepath: &str, filecontent: &str, mode: u32) -> io::Result<()> {
     let mut file = OpenOptions::new()
-        .mode(0o644)
+        .mode(mode)
         .write(true)
         .create(true)
         .truncate(true)
@@ -0,0 +1,248 @@
+//! Reports of a collection run, one test case per request block.
+use std::time::Duration;
+
+#[derive(Debug, Clone, Default)]
+pub struct TestCase {
+    pub classname: String,
+    pub name: String,
+    pub duration: Duration,
+    pub status: Option<u16>,
+    pub failures: Vec<String>,
+    pub error: Option<String>,
+    pub skipped: bool,
+}
+
+impl TestCase {
+    pub fn new(classname: &str, name: &str) -> Self {
+        TestCase {
+            classname: classname.to_string(),
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty() && self.error.is_none() && !self.skipped
+    }
+}
+
+/// Escape the value for XML 1.0, the C0 control chars are not allowed, even
+/// escaped, they are replaced by U+FFFD.
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < ' ' => escaped.push('\u{FFFD}'),
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Build a JUnit XML report.
+pub fn junit(suite: &str, cases: &[TestCase]) -> String {
+    let failures = cases.iter().filter(|c| !c.failures.is_empty()).count();
+    let errors = cases.iter().filter(|c| c.error.is_some()).count();
+    let skipped = cases.iter().filter(|c| c.skipped).count();
+    let time: f64 = cases.iter().map(|c| c.duration.as_secs_f64()).sum();
+
+    let mut report = String::new();
+    report.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    report.push_str(
+        format!(
+            "<testsuites name=\"rustaman\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+            cases.len(), failures, errors, skipped, time
+        )
+        .as_str(),
+    );
+    report.push_str(
+        format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+            escape_xml(suite), cases.len(), failures, errors, skipped, time
+        )
+        .as_str(),
+    );
+    for case in cases.iter() {
+        report.push_str(
+            format!(
+                "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\">\n",
+                escape_xml(case.classname.as_str()),
+                escape_xml(case.name.as_str()),
+                case.duration.as_secs_f64()
+            )
+            .as_str(),
+        );
+        if !case.failures.is_empty() {
+            report.push_str(
+                format!(
+                    "      <failure message=\"{} expectation(s) failed\" type=\"AssertionError\">{}</failure>\n",
+                    case.failures.len(),
+                    escape_xml(case.failures.join("\n").as_str())
+                )
+                .as_str(),
+            );
+        }
+        if let Some(error) = &case.error {
+            report.push_str(
+                format!(
+                    "      <error message=\"{}\" type=\"Error\"/>\n",
+                    escape_xml(error.as_str())
+                )
+                .as_str(),
+            );
+        }
+        if case.skipped {
+            report.push_str("      <skipped/>\n");
+        }
+        if let Some(status) = case.status {
+            report
+                .push_str(format!("      <system-out>status: {}</system-out>\n", status).as_str());
+        }
+        report.push_str("    </testcase>\n");
+    }
+    report.push_str("  </testsuite>\n");
+    report.push_str("</testsuites>\n");
+    report
+}
+
+fn yaml_string(value: &str) -> String {
+    serde_json::Value::String(value.to_string()).to_string()
+}
+
+/// Build a TAP version 13 report.
+pub fn tap(cases: &[TestCase]) -> String {
+    let mut report = String::new();
+    report.push_str("TAP version 13\n");
+    report.push_str(format!("1..{}\n", cases.len()).as_str());
+    for (idx, case) in cases.iter().enumerate() {
+        let name = case.name.replace('#', "\\#");
+        if case.skipped {
+            report.push_str(
+                format!("ok {} - {} # SKIP previous request failed\n", idx + 1, name).as_str(),
+            );
+            continue;
+        }
+        let result = if case.passed() { "ok" } else { "not ok" };
+        report.push_str(format!("{} {} - {}\n", result, idx + 1, name).as_str());
+        report.push_str("  ---\n");
+        if let Some(status) = case.status {
+            report.push_str(format!("  status: {}\n", status).as_str());
+        }
+        report.push_str(format!("  duration_ms: {}\n", case.duration.as_millis()).as_str());
+        if let Some(error) = &case.error {
+            report.push_str(format!("  error: {}\n", yaml_string(error.as_str())).as_str());
+        }
+        if !case.failures.is_empty() {
+            report.push_str("  failures:\n");
+            for failure in case.failures.iter() {
+                report.push_str(format!("    - {}\n", yaml_string(failure.as_str())).as_str());
+            }
+        }
+        report.push_str("  ...\n");
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cases() -> Vec<TestCase> {
+        let mut passed = TestCase::new("Users", "Users #1");
+        passed.status = Some(200);
+        passed.duration = Duration::from_millis(1500);
+        let mut failed = TestCase::new("Users", "Users #2");
+        failed.status = Some(404);
+        failed.failures = vec!["status == 200: got 404".to_string()];
+        let mut skipped = TestCase::new("Users", "Users #3");
+        skipped.skipped = true;
+        let mut error = TestCase::new("Login <admin>", "Login #1");
+        error.error = Some("Connection refused\u{1b}[0m".to_string());
+        vec![passed, failed, skipped, error]
+    }
+
+    #[test]
+    fn test_passed() {
+        let passed: Vec<bool> = cases().iter().map(|case| case.passed()).collect();
+        assert_eq!(passed, vec![true, false, false, false]);
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(
+            escape_xml("<a href=\"x\">'&'</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&apos;&amp;&apos;&lt;/a&gt;"
+        );
+        assert_eq!(escape_xml("a\tb\r\nc"), "a\tb\r\nc");
+        assert_eq!(escape_xml("a\u{0}b\u{1b}c"), "a\u{FFFD}b\u{FFFD}c");
+        assert_eq!(escape_xml("café ✓"), "café ✓");
+    }
+
+    #[test]
+    fn test_junit() {
+        assert_eq!(
+            junit("My <workspace>", cases().as_slice()),
+            [
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>",
+                "<testsuites name=\"rustaman\" tests=\"4\" failures=\"1\" errors=\"1\" skipped=\"1\" time=\"1.500\">",
+                "  <testsuite name=\"My &lt;workspace&gt;\" tests=\"4\" failures=\"1\" errors=\"1\" skipped=\"1\" time=\"1.500\">",
+                "    <testcase classname=\"Users\" name=\"Users #1\" time=\"1.500\">",
+                "      <system-out>status: 200</system-out>",
+                "    </testcase>",
+                "    <testcase classname=\"Users\" name=\"Users #2\" time=\"0.000\">",
+                "      <failure message=\"1 expectation(s) failed\" type=\"AssertionError\">status == 200: got 404</failure>",
+                "      <system-out>status: 404</system-out>",
+                "    </testcase>",
+                "    <testcase classname=\"Users\" name=\"Users #3\" time=\"0.000\">",
+                "      <skipped/>",
+                "    </testcase>",
+                "    <testcase classname=\"Login &lt;admin&gt;\" name=\"Login #1\" time=\"0.000\">",
+                "      <error message=\"Connection refused\u{FFFD}[0m\" type=\"Error\"/>",
+                "    </testcase>",
+                "  </testsuite>",
+                "</testsuites>",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_tap() {
+        let mut cases = cases();
+        cases[0].name = "Users #1 # first".to_string();
+        assert_eq!(
+            tap(cases.as_slice()),
+            [
+                "TAP version 13",
+                "1..4",
+                "ok 1 - Users \\#1 \\# first",
+                "  ---",
+                "  status: 200",
+                "  duration_ms: 1500",
+                "  ...",
+                "not ok 2 - Users \\#2",
+                "  ---",
+                "  status: 404",
+                "  duration_ms: 0",
+                "  failures:",
+                "    - \"status == 200: got 404\"",
+                "  ...",
+                "ok 3 - Users \\#3 # SKIP previous request failed",
+                "not ok 4 - Login \\#1",
+                "  ---",
+                "  duration_ms: 0",
+                "  error: \"Connection refused\\u001b[0m\"",
+                "  ...",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
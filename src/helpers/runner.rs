@@ -0,0 +1,70 @@
+use super::capture;
+use super::expect::Assertion;
+use super::http::{self, HttpResponse};
+use super::httpparser::{self, HttpRequest};
+use crate::errors::RustamanResult;
+use crate::models::Environment;
+
+#[derive(Debug, Clone)]
+pub enum RunEvent {
+    /// The obfuscated request, and the length of the frame sent.
+    Sending(HttpRequest, usize),
+    Received(HttpResponse),
+    SendingError(String),
+    /// The expectations of the request checked against its response.
+    Asserted(Vec<Assertion>),
+}
+
+/// Run the requests of a template one after the other.
+///
+/// The values captured in a response are available to render the
+/// following requests. The errors of the template are located in it.
+pub async fn run<F>(template: String, environ: Environment, mut on_event: F) -> RustamanResult<()>
+where
+    F: FnMut(RunEvent),
+{
+    let mut context = environ.parsed_payload()?;
+    let mut secrets = environ.obfuscated_string();
+    for (block, (first_line, template)) in httpparser::split_template_lines(template.as_str())
+        .iter()
+        .enumerate()
+    {
+        debug!("Processing {:?}", template);
+        let httpreq = httpparser::load_template_with_context(template.as_str(), &context)
+            .map_err(|err| err.in_block(block, *first_line))?;
+        on_event(RunEvent::Sending(
+            httpreq.obfuscate(secrets.as_slice()),
+            httpreq.http_frame().len(),
+        ));
+
+        let response = match http::send(&httpreq).await {
+            Ok(response) => response,
+            Err(err) => {
+                on_event(RunEvent::SendingError(err.to_string()));
+                return Err(err);
+            }
+        };
+        debug!("Response: {:?}", response);
+        on_event(RunEvent::Received(response.clone()));
+        if !httpreq.expects().is_empty() {
+            let assertions = httpreq
+                .expects()
+                .iter()
+                .map(|expect| expect.check(&response))
+                .collect();
+            on_event(RunEvent::Asserted(assertions));
+        }
+
+        for capture in httpreq.captures() {
+            let value = capture.extract(&response)?;
+            if let Some(secret) = capture::secret(&value) {
+                secrets.push(secret.to_string());
+            }
+            capture::inject(&mut context, capture.name(), value)?;
+            debug!("Captured {}", capture.name());
+        }
+        debug!("Done with the request");
+    }
+    debug!("Done with all the requests");
+    Ok(())
+}
@@ -1,12 +1,20 @@
+use relm4::gtk::{self, prelude::*};
 use sourceview5::{self, prelude::*};
 
+/// The color scheme of the application is dark, unless the light one is forced.
+pub fn is_light_scheme() -> bool {
+    matches!(
+        adw::StyleManager::default().color_scheme(),
+        adw::ColorScheme::ForceLight
+    )
+}
+
 pub fn create_buffer(language: &str) -> sourceview5::Buffer {
     let buffer = sourceview5::Buffer::new(None);
     buffer.set_highlight_syntax(true);
 
     let langmngr = sourceview5::LanguageManager::default();
     let stmngr = sourceview5::StyleSchemeManager::default();
-    let sm = adw::StyleManager::default();
 
     if let Some(ref language) = langmngr.language(language) {
         buffer.set_language(Some(language));
@@ -17,9 +25,10 @@ pub fn create_buffer(language: &str) -> sourceview5::Buffer {
             langmngr.search_path()
         )
     }
-    let scheme_id = match sm.color_scheme() {
-        adw::ColorScheme::ForceLight => "rustaman-light".to_owned(),
-        _ => "rustaman-dark".to_owned(),
+    let scheme_id = if is_light_scheme() {
+        "rustaman-light".to_owned()
+    } else {
+        "rustaman-dark".to_owned()
     };
 
     if let Some(ref scheme) = stmngr.scheme(scheme_id.as_str()) {
@@ -33,3 +42,60 @@ pub fn create_buffer(language: &str) -> sourceview5::Buffer {
     }
     buffer
 }
+
+/// Change the language used to highlight the buffer, `None` for plain text.
+pub fn set_language(buffer: &sourceview5::Buffer, language: Option<&str>) {
+    let language = language.and_then(|language| {
+        let langmngr = sourceview5::LanguageManager::default();
+        let found = langmngr.language(language);
+        if found.is_none() {
+            error!(
+                "Can't find {}.lang lang in {:?}",
+                language,
+                langmngr.search_path()
+            )
+        }
+        found
+    });
+    buffer.set_language(language.as_ref());
+}
+
+/// The category of the source marks of the errors.
+pub const ERROR_MARK: &str = "error";
+const ERROR_TAG: &str = "error";
+
+/// Mark the line of the error, the text is underlined from the column.
+///
+/// The lines and columns start at 1.
+pub fn mark_error(buffer: &sourceview5::Buffer, line: usize, column: Option<usize>) {
+    let line = line.saturating_sub(1) as i32;
+    let start = match column {
+        Some(column) => buffer.iter_at_line_offset(line, column.saturating_sub(1) as i32),
+        None => buffer.iter_at_line(line),
+    };
+    let start = match start.or_else(|| buffer.iter_at_line(line)) {
+        Some(start) => start,
+        None => return,
+    };
+    let mut end = start.clone();
+    if !end.ends_line() {
+        end.forward_to_line_end();
+    }
+    if buffer.tag_table().lookup(ERROR_TAG).is_none() {
+        buffer.create_tag(
+            Some(ERROR_TAG),
+            &[("underline", &gtk::pango::Underline::Error)],
+        );
+    }
+    buffer.apply_tag_by_name(ERROR_TAG, &start, &end);
+    buffer.create_source_mark(None, ERROR_MARK, &start);
+}
+
+/// Remove the marks of the errors.
+pub fn clear_errors(buffer: &sourceview5::Buffer) {
+    let (start, end) = buffer.bounds();
+    buffer.remove_source_marks(&start, &end, Some(ERROR_MARK));
+    if buffer.tag_table().lookup(ERROR_TAG).is_some() {
+        buffer.remove_tag_by_name(ERROR_TAG, &start, &end);
+    }
+}
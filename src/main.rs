@@ -6,13 +6,15 @@ extern crate serde_derive;
 use std::env;
 use std::io::Write;
 
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum};
 
 use relm4::gtk;
 use relm4::prelude::*;
 use relm4_icons;
 use sourceview5::{LanguageManager, StyleSchemeManager};
 
+mod cli;
+mod converters;
 mod errors;
 mod helpers;
 mod models;
@@ -27,15 +29,57 @@ enum ColorScheme {
     Auto,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run a request of the workspace and print the response, without the GUI
+    Run {
+        /// name of the environment, the first one by default
+        #[arg(short, long)]
+        env: Option<String>,
+
+        /// print the status line and the headers of the response
+        #[arg(short, long)]
+        include: bool,
+
+        /// print the requests sent on stderr
+        #[arg(short, long)]
+        verbose: bool,
+
+        /// name of the request to run
+        request: String,
+    },
+    /// Run the requests of the workspace and write a test report
+    Test {
+        /// name of the environment, the first one by default
+        #[arg(short, long)]
+        env: Option<String>,
+
+        /// run only the requests whose name matches this regex
+        #[arg(short, long)]
+        filter: Option<String>,
+
+        /// format of the report
+        #[arg(short, long, value_enum, default_value = "tap")]
+        report: cli::ReportFormat,
+
+        /// filepath of the report, printed on stdout by default
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+}
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
     /// filepath to the workspace to load
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     workspace: Option<String>,
 
     #[arg(short, long, value_enum)]
     color_scheme: Option<ColorScheme>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
 fn init_gtk(color_scheme: Option<ColorScheme>) -> RustamanResult<()> {
@@ -79,8 +123,48 @@ fn init_gtk(color_scheme: Option<ColorScheme>) -> RustamanResult<()> {
     Ok(())
 }
 
+fn run_command(workspace: Option<String>, command: Command) -> RustamanResult<()> {
+    let filepath = match workspace {
+        Some(filepath) => filepath,
+        None => helpers::path::workspace("workspace.json")?
+            .to_str()
+            .unwrap()
+            .to_owned(),
+    };
+    let workspace = models::Workspace::from_file(filepath.as_str())?;
+    match command {
+        Command::Run {
+            env,
+            include,
+            verbose,
+            request,
+        } => cli::run_request(
+            &workspace,
+            env.as_deref(),
+            request.as_str(),
+            include,
+            verbose,
+        ),
+        Command::Test {
+            env,
+            filter,
+            report,
+            output,
+        } => cli::run_collection(
+            &workspace,
+            env.as_deref(),
+            filter.as_deref(),
+            report,
+            output.as_deref(),
+        ),
+    }
+}
+
 fn run() -> RustamanResult<()> {
     let args = Args::parse();
+    if let Some(command) = args.command {
+        return run_command(args.workspace, command);
+    }
     init_gtk(args.color_scheme)?;
 
     let workspace = if let Some(filepath) = args.workspace {
@@ -51,6 +51,33 @@ impl Environment {
         self.payload = payload.to_owned()
     }
 
+    /// Append the variables of the payload missing in the environment,
+    /// the existing values and the comments are kept.
+    pub fn merge_payload(&mut self, payload: &str) -> RustamanResult<()> {
+        let current = self.parsed_payload()?;
+        let imported: serde_yaml_ng::Value = serde_yaml_ng::from_str(payload)?;
+        let mut missing = serde_yaml_ng::Mapping::new();
+        if let serde_yaml_ng::Value::Mapping(imported) = imported {
+            for (key, value) in imported.into_iter() {
+                if !current
+                    .as_mapping()
+                    .map_or(false, |mapping| mapping.contains_key(&key))
+                {
+                    missing.insert(key, value);
+                }
+            }
+        }
+        if missing.is_empty() {
+            return Ok(());
+        }
+        if !self.payload.ends_with('\n') {
+            self.payload.push('\n');
+        }
+        self.payload
+            .push_str(serde_yaml_ng::to_string(&missing)?.as_str());
+        Ok(())
+    }
+
     pub fn active(&self) -> bool {
         match self.status {
             Status::Active => true,
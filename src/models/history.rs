@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::vec::Vec;
+
+use crate::helpers::base64;
+use crate::helpers::http::HttpResponse;
+
+/// Number of responses kept per request.
+pub const MAX_HISTORY: usize = 20;
+
+/// Size of the body kept in the history, the larger bodies are truncated.
+pub const MAX_HISTORY_BODY: usize = 256 * 1024;
+
+/// The longest prefix of the text that fits in `max` bytes.
+fn truncate(text: &str, max: usize) -> &str {
+    if text.len() <= max {
+        return text;
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryEntry {
+    /// Milliseconds since the epoch.
+    timestamp: u64,
+    /// The environment of the run, if one was selected.
+    #[serde(default)]
+    environment_id: Option<usize>,
+    version: String,
+    status: u16,
+    reason: String,
+    headers: Vec<(String, String)>,
+    body: String,
+    /// The base64 encoded body, for a binary body.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    binary_body: Option<String>,
+    /// Set when the body was larger than `MAX_HISTORY_BODY`.
+    #[serde(default)]
+    truncated: bool,
+    duration_ms: u64,
+}
+
+impl HistoryEntry {
+    pub fn new(response: &HttpResponse, environment_id: Option<usize>) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let raw_body = response.raw_body();
+        HistoryEntry {
+            timestamp: timestamp.as_millis() as u64,
+            environment_id,
+            version: response.version().to_owned(),
+            status: response.status(),
+            reason: response.reason().to_owned(),
+            headers: response.headers().to_vec(),
+            body: truncate(response.body(), MAX_HISTORY_BODY).to_owned(),
+            binary_body: if response.is_binary() {
+                Some(base64::encode(
+                    &raw_body[..raw_body.len().min(MAX_HISTORY_BODY)],
+                ))
+            } else {
+                None
+            },
+            truncated: raw_body.len().max(response.body().len()) > MAX_HISTORY_BODY,
+            duration_ms: response.elapsed().as_millis() as u64,
+        }
+    }
+
+    pub fn timestamp(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.timestamp)
+    }
+    pub fn environment_id(&self) -> Option<usize> {
+        self.environment_id
+    }
+    pub fn version(&self) -> &str {
+        self.version.as_str()
+    }
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+    pub fn reason(&self) -> &str {
+        self.reason.as_str()
+    }
+    pub fn headers(&self) -> &[(String, String)] {
+        self.headers.as_slice()
+    }
+    pub fn body(&self) -> &str {
+        self.body.as_str()
+    }
+    pub fn is_binary(&self) -> bool {
+        self.binary_body.is_some()
+    }
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+    /// The body as it has been received.
+    pub fn raw_body(&self) -> Vec<u8> {
+        match self.binary_body.as_ref() {
+            Some(encoded) => base64::decode(encoded).unwrap_or_default(),
+            None => self.body.as_bytes().to_vec(),
+        }
+    }
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.duration_ms)
+    }
+}
+
+pub type History = Vec<HistoryEntry>;
+
+/// The histories of the requests, by request id.
+pub type Histories = BTreeMap<usize, History>;
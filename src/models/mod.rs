@@ -1,9 +1,11 @@
 mod agent;
 mod environment;
+mod history;
 mod status;
 mod template;
 mod workspace;
 
 pub use self::agent::USER_AGENT;
-pub use self::environment::{Environment, Environments};
+pub use self::environment::{Environment, Environments, DEFAULT_ENVIRONMENT};
+pub use self::history::HistoryEntry;
 pub use self::workspace::{Request, Workspace};
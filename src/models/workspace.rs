@@ -3,6 +3,7 @@ use std::vec::Vec;
 use super::super::errors::RustamanResult;
 use super::super::helpers::path;
 use super::environment::{Environment, Environments, DEFAULT_ENVIRONMENT};
+use super::history::{Histories, HistoryEntry, MAX_HISTORY};
 use super::status::Status;
 use super::template::Template;
 
@@ -61,6 +62,13 @@ pub struct Payload {
 pub struct Workspace {
     filepath: String,
     payload: Payload,
+    /// The responses are stored apart, they may be large and contain secrets.
+    history: Histories,
+}
+
+/// The file of the history, next to the workspace file.
+fn history_filepath(filepath: &str) -> String {
+    format!("{}.history.json", filepath.trim_end_matches(".json"))
 }
 
 impl Workspace {
@@ -72,6 +80,7 @@ impl Workspace {
                 requests: vec![],
                 environments: vec![Environment::new(1, "Dev", DEFAULT_ENVIRONMENT)],
             },
+            history: Histories::new(),
         }
     }
 
@@ -88,6 +97,7 @@ impl Workspace {
         let workspace = Workspace {
             payload,
             filepath: filepath.to_string(),
+            history: Workspace::load_history(filepath),
         };
         info!("Workspace loaded from file {}", filepath);
         Ok(workspace)
@@ -120,12 +130,41 @@ impl Workspace {
         Ok(())
     }
 
+    /// Load the history of the workspace, it is lost if it cannot be read.
+    fn load_history(filepath: &str) -> Histories {
+        let filepath = history_filepath(filepath);
+        let content = match std::fs::read_to_string(filepath.as_str()) {
+            Ok(content) => content,
+            Err(err) => {
+                info!("No history loaded from file {}: {}", filepath, err);
+                return Histories::new();
+            }
+        };
+        serde_json::from_str(content.as_str()).unwrap_or_else(|err| {
+            error!("Invalid history in file {}: {}", filepath, err);
+            Histories::new()
+        })
+    }
+
+    fn sync_history(&self) -> RustamanResult<()> {
+        let filepath = history_filepath(self.filepath());
+        info!("Writing history in file {}", filepath);
+        let filecontent = serde_json::to_string(&self.history)
+            .expect("Unable to save history, cannot serializing it to json");
+        path::write_private_file(filepath.as_str(), filecontent.as_str())?;
+        Ok(())
+    }
+
     pub fn safe_sync(&self) {
         self.sync().unwrap_or_else(|err| {
             error! {"Workspace not synchronized: {}", err}
         });
     }
 
+    pub fn name(&self) -> &str {
+        self.payload.name.as_str()
+    }
+
     fn filepath(&self) -> &str {
         self.filepath.as_str()
     }
@@ -143,6 +182,16 @@ impl Workspace {
         None
     }
 
+    /// The active request with the given name.
+    pub fn request_by_name(&self, name: &str) -> Option<&Request> {
+        for request in self.requests().iter() {
+            if request.active() && request.name() == name {
+                return Some(request);
+            }
+        }
+        None
+    }
+
     pub fn create_request(&mut self) -> &Request {
         let id = match self.payload.requests.last() {
             None => 1,
@@ -159,6 +208,14 @@ impl Workspace {
         self.payload.requests.last().unwrap()
     }
 
+    /// Create an active request, from an imported template.
+    pub fn import_request(&mut self, name: &str, template: &str) -> &Request {
+        let id = self.create_request().id();
+        self.set_request_name(id, name);
+        self.set_request_template(id, template);
+        self.request(id).unwrap()
+    }
+
     pub fn set_request_name(&mut self, id: usize, name: &str) {
         for request in &mut self.payload.requests {
             if request.id() == id {
@@ -191,6 +248,27 @@ impl Workspace {
         self.safe_sync();
     }
 
+    /// The responses received by the request, the oldest first.
+    pub fn history(&self, id: usize) -> &[HistoryEntry] {
+        self.history
+            .get(&id)
+            .map(|history| history.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Store a response in the history of the request, only the history file is written.
+    pub fn add_response(&mut self, id: usize, entry: HistoryEntry) {
+        let history = self.history.entry(id).or_default();
+        history.push(entry);
+        if history.len() > MAX_HISTORY {
+            let overflow = history.len() - MAX_HISTORY;
+            history.drain(..overflow);
+        }
+        self.sync_history().unwrap_or_else(|err| {
+            error! {"History not synchronized: {}", err}
+        });
+    }
+
     pub fn environments(&self) -> &[Environment] {
         self.payload.environments.as_slice()
     }
@@ -204,6 +282,16 @@ impl Workspace {
         None
     }
 
+    /// The active environment with the given name.
+    pub fn environment_by_name(&self, name: &str) -> Option<&Environment> {
+        for environment in self.environments().iter() {
+            if environment.active() && environment.name() == name {
+                return Some(environment);
+            }
+        }
+        None
+    }
+
     pub fn create_environment(&mut self, name: &str) -> &Environment {
         let id = match self.payload.environments.last() {
             None => 1,
@@ -216,6 +304,34 @@ impl Workspace {
         env
     }
 
+    /// Import an environment, an active environment with the same name is
+    /// reused, the missing variables are added to it.
+    pub fn import_environment(&mut self, name: &str, payload: &str) -> &Environment {
+        let existing = self
+            .environment_by_name(name)
+            .map(|environment| environment.id());
+        let id = match existing {
+            Some(id) => {
+                for environment in &mut self.payload.environments {
+                    if environment.id() == id {
+                        environment.merge_payload(payload).unwrap_or_else(|err| {
+                            error!("Environment {} not merged: {}", name, err);
+                        });
+                        break;
+                    }
+                }
+                self.safe_sync();
+                id
+            }
+            None => {
+                let id = self.create_environment(name).id();
+                self.set_environ_payload(id, payload);
+                id
+            }
+        };
+        self.environment(id).unwrap()
+    }
+
     pub fn set_environ_payload(&mut self, id: usize, payload: &str) {
         for environment in &mut self.payload.environments {
             if environment.id() == id {
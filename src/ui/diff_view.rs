@@ -0,0 +1,206 @@
+// Don't show GTK 4.10 deprecations.
+// We can't replace them without raising the GTK requirement to 4.10.
+#![allow(deprecated)]
+
+use relm4::gtk::prelude::*;
+use relm4::prelude::*;
+use relm4::{gtk, ComponentParts, ComponentSender};
+use sourceview5;
+
+use crate::helpers::diff::{self, RowKind};
+use crate::helpers::http::HttpResponse;
+use crate::helpers::sourceview::{create_buffer, is_light_scheme};
+
+#[derive(Debug, Clone)]
+pub enum DiffMsg {
+    /// The title and the response of both sides, the left one is the reference.
+    Compare(String, HttpResponse, String, HttpResponse),
+}
+
+pub struct DiffView {}
+
+pub struct Widgets {
+    left_title: gtk::Label,
+    right_title: gtk::Label,
+    left_buffer: sourceview5::Buffer,
+    right_buffer: sourceview5::Buffer,
+    changes: gtk::Label,
+}
+
+/// The backgrounds of the removed, added and changed lines on a light scheme.
+const LIGHT_BACKGROUNDS: [(&str, &str); 3] = [
+    ("removed", "#ffd7d5"),
+    ("added", "#d4f5d9"),
+    ("changed", "#fcefc0"),
+];
+
+/// The backgrounds of the removed, added and changed lines on a dark scheme.
+const DARK_BACKGROUNDS: [(&str, &str); 3] = [
+    ("removed", "#5c1f1f"),
+    ("added", "#1f4d2b"),
+    ("changed", "#5c4d1f"),
+];
+
+fn create_diff_buffer() -> sourceview5::Buffer {
+    let buffer = create_buffer("json");
+    let backgrounds = if is_light_scheme() {
+        LIGHT_BACKGROUNDS
+    } else {
+        DARK_BACKGROUNDS
+    };
+    for (tag, background) in backgrounds {
+        buffer.create_tag(Some(tag), &[("background", &background)]);
+    }
+    buffer
+}
+
+fn highlight_line(buffer: &sourceview5::Buffer, line: i32, tag: &str) {
+    if let Some(start) = buffer.iter_at_line(line) {
+        let mut end = start.clone();
+        end.forward_line();
+        buffer.apply_tag_by_name(tag, &start, &end);
+    }
+}
+
+fn create_pane(
+    title: &gtk::Label,
+    buffer: &sourceview5::Buffer,
+) -> (gtk::Box, gtk::ScrolledWindow) {
+    let view = sourceview5::View::with_buffer(buffer);
+    view.set_editable(false);
+    view.set_monospace(true);
+    view.set_margin_all(10);
+    let scrolled = gtk::ScrolledWindow::new();
+    scrolled.set_child(Some(&view));
+    relm4::view! {
+        pane = gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+            set_hexpand: true,
+            set_vexpand: true,
+            #[local_ref]
+            title -> gtk::Label {
+                set_xalign: 0.0,
+                set_margin_all: 5,
+            },
+            #[local_ref]
+            scrolled -> gtk::ScrolledWindow {
+                set_hexpand: true,
+                set_vexpand: true,
+            }
+        }
+    }
+    (pane, scrolled)
+}
+
+impl Component for DiffView {
+    type Init = ();
+    type Input = DiffMsg;
+    type Output = ();
+    type CommandOutput = ();
+    type Widgets = Widgets;
+    type Root = gtk::Box;
+
+    fn init_root() -> Self::Root {
+        gtk::Box::new(gtk::Orientation::Vertical, 5)
+    }
+
+    fn init(
+        _request: Self::Init,
+        root: Self::Root,
+        _sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let left_title = gtk::Label::new(Some("Select two responses in the history to compare"));
+        let right_title = gtk::Label::new(None);
+        let left_buffer = create_diff_buffer();
+        let right_buffer = create_diff_buffer();
+        let changes = gtk::Label::new(None);
+        let (left_pane, left_scrolled) = create_pane(&left_title, &left_buffer);
+        let (right_pane, right_scrolled) = create_pane(&right_title, &right_buffer);
+        // the rows are aligned, both sides scroll together.
+        right_scrolled.set_vadjustment(Some(&left_scrolled.vadjustment()));
+
+        relm4::view! {
+            #[local_ref]
+            root -> gtk::Box {
+                gtk::Paned::new(gtk::Orientation::Horizontal) {
+                    set_wide_handle: true,
+                    set_start_child: Some(&left_pane),
+                    set_end_child: Some(&right_pane),
+                },
+                gtk::ScrolledWindow {
+                    set_hexpand: true,
+                    set_max_content_height: 150,
+                    set_propagate_natural_height: true,
+                    #[local_ref]
+                    changes -> gtk::Label {
+                        set_xalign: 0.0,
+                        set_selectable: true,
+                        set_margin_all: 5,
+                    }
+                }
+            }
+        }
+
+        ComponentParts {
+            model: DiffView {},
+            widgets: Widgets {
+                left_title,
+                right_title,
+                left_buffer,
+                right_buffer,
+                changes,
+            },
+        }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        message: Self::Input,
+        _sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match message {
+            DiffMsg::Compare(left_title, left, right_title, right) => {
+                let diff = diff::diff_responses(&left, &right);
+                widgets.left_title.set_text(left_title.as_str());
+                widgets.right_title.set_text(right_title.as_str());
+
+                let mut left_text = String::new();
+                let mut right_text = String::new();
+                for row in diff.rows.iter() {
+                    left_text.push_str(row.left.as_deref().unwrap_or(""));
+                    left_text.push('\n');
+                    right_text.push_str(row.right.as_deref().unwrap_or(""));
+                    right_text.push('\n');
+                }
+                widgets.left_buffer.set_text(left_text.as_str());
+                widgets.right_buffer.set_text(right_text.as_str());
+                for (line, row) in diff.rows.iter().enumerate() {
+                    let line = line as i32;
+                    match row.kind {
+                        RowKind::Same => {}
+                        RowKind::Changed => {
+                            highlight_line(&widgets.left_buffer, line, "changed");
+                            highlight_line(&widgets.right_buffer, line, "changed");
+                        }
+                        RowKind::Removed => highlight_line(&widgets.left_buffer, line, "removed"),
+                        RowKind::Added => highlight_line(&widgets.right_buffer, line, "added"),
+                    }
+                }
+
+                let changes = match diff.json_changes {
+                    Some(changes) if changes.is_empty() => "The JSON bodies are equal".to_string(),
+                    Some(changes) => changes
+                        .iter()
+                        .map(|change| change.to_string())
+                        .collect::<Vec<String>>()
+                        .join("\n"),
+                    None => "".to_string(),
+                };
+                widgets.changes.set_text(changes.as_str());
+                widgets.changes.set_visible(!changes.is_empty());
+            }
+        }
+    }
+}
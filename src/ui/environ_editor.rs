@@ -11,7 +11,10 @@ use crate::helpers::sourceview::create_buffer;
 use crate::models::Environment;
 
 #[derive(Debug, Clone)]
-pub enum EnvironmentMsg {}
+pub enum EnvironmentMsg {
+    /// The payload changed in the workspace, by an import.
+    SetPayload(String),
+}
 
 #[derive(Debug, Clone)]
 pub enum EnvironmentOutput {
@@ -98,13 +101,15 @@ impl Component for EnvironmentEditor {
         }
     }
 
-    fn update(
+    fn update_with_view(
         &mut self,
-        _message: Self::Input,
+        widgets: &mut Self::Widgets,
+        message: Self::Input,
         _sender: ComponentSender<Self>,
         _root: &Self::Root,
     ) {
+        match message {
+            EnvironmentMsg::SetPayload(payload) => widgets.buffer.set_text(payload.as_str()),
+        }
     }
-
-    fn update_view(&self, _widgets: &mut Self::Widgets, _sender: ComponentSender<Self>) {}
 }
@@ -8,7 +8,7 @@ use relm4::{gtk, ComponentParts, ComponentSender};
 use relm4_icons::icon_names;
 
 use crate::models::{Environment, Environments};
-use crate::ui::environ_editor::{EnvironmentEditor, EnvironmentOutput};
+use crate::ui::environ_editor::{EnvironmentEditor, EnvironmentMsg, EnvironmentOutput};
 
 #[derive(Debug, Clone)]
 pub enum EnvironmentsMsg {
@@ -16,6 +16,7 @@ pub enum EnvironmentsMsg {
     CancelCreate,
     CreateEnvironment(String),
     EnvironmentCreated(Environment),
+    EnvironmentUpdated(Environment),
     RenamingEnvironment(usize),
     RenameEnvironment(usize, String),
     CancelRename(usize),
@@ -57,6 +58,13 @@ impl EnvironmentsTabs {
         }
         return "".to_string();
     }
+    /// The payload in the editor of the environment, it may not be saved yet.
+    pub fn environment_payload(&self, env_id: usize) -> Option<String> {
+        self.editors
+            .iter()
+            .find(|ed| ed.widgets().get_environment_id() == env_id)
+            .map(|ed| ed.widgets().get_environment())
+    }
 }
 
 pub struct Widgets {
@@ -288,6 +296,17 @@ impl Component for EnvironmentsTabs {
                     entry.hide();
                 }
             }
+            EnvironmentsMsg::EnvironmentUpdated(environment) => {
+                let editor = self
+                    .editors
+                    .iter()
+                    .find(|ed| ed.widgets().get_environment_id() == environment.id());
+                if let Some(editor) = editor {
+                    editor.emit(EnvironmentMsg::SetPayload(
+                        environment.payload().to_string(),
+                    ));
+                }
+            }
             EnvironmentsMsg::DeleteEnvironment(env_id) => sender
                 .output_sender()
                 .emit(EnvironmentsOutput::DeleteEnvironment(env_id)),
@@ -0,0 +1,143 @@
+// Don't show GTK 4.10 deprecations.
+// We can't replace them without raising the GTK requirement to 4.10.
+#![allow(deprecated)]
+
+use relm4::gtk::prelude::*;
+use relm4::prelude::*;
+use relm4::{gtk, ComponentParts, ComponentSender};
+
+#[derive(Debug, Clone)]
+pub enum HistoryMsg {
+    /// The index of the entries in the request history, and their labels.
+    Show(Vec<(usize, String)>),
+    Clear,
+    Activated(i32),
+    Compare,
+}
+
+#[derive(Debug, Clone)]
+pub enum HistoryOutput {
+    Open(usize),
+    /// Compare two entries, the older first.
+    Compare(usize, usize),
+}
+
+pub struct HistoryPanel {
+    entries: Vec<usize>,
+}
+
+pub struct Widgets {
+    list_box: gtk::ListBox,
+}
+
+impl HistoryPanel {
+    fn clear(&mut self, list_box: &gtk::ListBox) {
+        while let Some(row) = list_box.first_child() {
+            list_box.remove(&row);
+        }
+        self.entries.clear();
+    }
+}
+
+impl Component for HistoryPanel {
+    type Init = ();
+    type Input = HistoryMsg;
+    type Output = HistoryOutput;
+    type CommandOutput = ();
+    type Widgets = Widgets;
+    type Root = gtk::Box;
+
+    fn init_root() -> Self::Root {
+        gtk::Box::new(gtk::Orientation::Vertical, 5)
+    }
+
+    fn init(
+        _request: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let list_box = gtk::ListBox::new();
+
+        relm4::view! {
+            #[local_ref]
+            root -> gtk::Box {
+                gtk::Button {
+                    set_label: "Compare",
+                    set_tooltip_text: Some("Compare the two selected responses, Ctrl+Click to select them"),
+                    set_halign: gtk::Align::Start,
+                    set_margin_all: 5,
+                    connect_clicked[sender] => move |_| {
+                        sender.input(HistoryMsg::Compare);
+                    },
+                },
+                gtk::ScrolledWindow {
+                    set_hexpand: true,
+                    set_vexpand: true,
+                    #[local_ref]
+                    list_box -> gtk::ListBox {
+                        set_selection_mode: gtk::SelectionMode::Multiple,
+                        set_activate_on_single_click: false,
+                        connect_row_activated[sender] => move |_, row| {
+                            sender.input(HistoryMsg::Activated(row.index()));
+                        },
+                    }
+                }
+            }
+        }
+
+        ComponentParts {
+            model: HistoryPanel { entries: vec![] },
+            widgets: Widgets { list_box },
+        }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        message: Self::Input,
+        sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match message {
+            HistoryMsg::Show(entries) => {
+                self.clear(&widgets.list_box);
+                for (index, label) in entries {
+                    let label = gtk::Label::new(Some(label.as_str()));
+                    label.set_xalign(0.0);
+                    label.set_margin_all(5);
+                    widgets.list_box.append(&label);
+                    self.entries.push(index);
+                }
+            }
+            HistoryMsg::Clear => self.clear(&widgets.list_box),
+            HistoryMsg::Activated(position) => {
+                if let Some(index) = usize::try_from(position)
+                    .ok()
+                    .and_then(|position| self.entries.get(position))
+                {
+                    sender.output(HistoryOutput::Open(*index)).unwrap();
+                }
+            }
+            HistoryMsg::Compare => {
+                let mut selected: Vec<usize> = widgets
+                    .list_box
+                    .selected_rows()
+                    .iter()
+                    .filter_map(|row| usize::try_from(row.index()).ok())
+                    .filter_map(|position| self.entries.get(position).copied())
+                    .collect();
+                selected.sort();
+                if let [older, newer] = selected.as_slice() {
+                    sender
+                        .output(HistoryOutput::Compare(*older, *newer))
+                        .unwrap();
+                } else {
+                    info!(
+                        "Select two responses to compare, {} selected",
+                        selected.len()
+                    );
+                }
+            }
+        }
+    }
+}
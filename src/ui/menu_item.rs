@@ -2,6 +2,8 @@ use relm4::factory::{DynamicIndex, FactoryComponent, FactorySender};
 use relm4::gtk::prelude::*;
 use relm4::{gtk, RelmWidgetExt};
 
+use super::super::converters::command::CommandFormat;
+use super::super::converters::snippet::SnippetLanguage;
 use super::super::models::Request;
 
 #[derive(Debug)]
@@ -17,6 +19,7 @@ pub struct MenuItem {
     selected: bool,
     request: Request,
     mode: MenuMode,
+    keep_placeholders: bool,
 }
 
 impl MenuItem {
@@ -67,6 +70,9 @@ pub enum MenuItemMsg {
     RenameRequest,
     CancelRenameRequest,
     ValidateRenameRequest,
+    CopyAs(CommandFormat),
+    KeepPlaceholders(bool),
+    CopySnippet(SnippetLanguage),
 }
 
 #[derive(Debug, Clone)]
@@ -74,6 +80,8 @@ pub enum MenuItemOutput {
     DeleteRequest(usize),
     TogglingRequest(usize, bool),
     RenameRequest(usize, String),
+    CopyAs(usize, CommandFormat),
+    CopySnippet(usize, SnippetLanguage, bool),
 }
 
 pub struct MenuItemWidgets {
@@ -108,6 +116,7 @@ impl FactoryComponent for MenuItem {
             selected: false,
             visible: true,
             mode,
+            keep_placeholders: false,
         }
     }
 
@@ -187,6 +196,39 @@ impl FactoryComponent for MenuItem {
                                     gtk::Button {
                                         set_label: "Delete",
                                         connect_clicked => MenuItemMsg::DeleteRequest,
+                                    },
+                                    gtk::Separator {},
+                                    gtk::Button {
+                                        set_label: "Copy as curl",
+                                        connect_clicked => MenuItemMsg::CopyAs(CommandFormat::Curl),
+                                    },
+                                    gtk::Button {
+                                        set_label: "Copy as HTTPie",
+                                        connect_clicked => MenuItemMsg::CopyAs(CommandFormat::Httpie),
+                                    },
+                                    gtk::Button {
+                                        set_label: "Copy as wget",
+                                        connect_clicked => MenuItemMsg::CopyAs(CommandFormat::Wget),
+                                    },
+                                    gtk::Separator {},
+                                    gtk::CheckButton {
+                                        set_label: Some("Keep variables"),
+                                        set_tooltip_text: Some("Keep the handlebars variables in the snippets"),
+                                        connect_toggled[sender] => move |btn| {
+                                            sender.input(MenuItemMsg::KeepPlaceholders(btn.is_active()));
+                                        },
+                                    },
+                                    gtk::Button {
+                                        set_label: "Copy as Rust",
+                                        connect_clicked => MenuItemMsg::CopySnippet(SnippetLanguage::Rust),
+                                    },
+                                    gtk::Button {
+                                        set_label: "Copy as Python",
+                                        connect_clicked => MenuItemMsg::CopySnippet(SnippetLanguage::Python),
+                                    },
+                                    gtk::Button {
+                                        set_label: "Copy as JavaScript",
+                                        connect_clicked => MenuItemMsg::CopySnippet(SnippetLanguage::JavaScript),
                                     }
                                 }
                             }
@@ -212,13 +254,20 @@ impl FactoryComponent for MenuItem {
         }
     }
 
-    fn update(&mut self, msg: Self::Input, _sender: FactorySender<Self>) {
+    fn update(&mut self, msg: Self::Input, sender: FactorySender<Self>) {
         info!("Update {:?}", msg);
         match msg {
             MenuItemMsg::RenameRequest => self.mode = MenuMode::Edit,
             MenuItemMsg::CancelRenameRequest => self.mode = MenuMode::Toggle,
             MenuItemMsg::ValidateRenameRequest => self.mode = MenuMode::Renaming,
             MenuItemMsg::DeleteRequest => self.mode = MenuMode::Deleting,
+            MenuItemMsg::CopyAs(format) => sender
+                .output_sender()
+                .emit(MenuItemOutput::CopyAs(self.request.id(), format)),
+            MenuItemMsg::KeepPlaceholders(value) => self.keep_placeholders = value,
+            MenuItemMsg::CopySnippet(language) => sender.output_sender().emit(
+                MenuItemOutput::CopySnippet(self.request.id(), language, self.keep_placeholders),
+            ),
         }
     }
 
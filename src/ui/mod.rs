@@ -1,5 +1,7 @@
+pub(crate) mod diff_view;
 pub(crate) mod environ_editor;
 pub(crate) mod environments;
+pub(crate) mod history;
 pub(crate) mod menu_item;
 pub(crate) mod request_editor;
 pub(crate) mod response_body;
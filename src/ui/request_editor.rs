@@ -7,13 +7,16 @@ use relm4::prelude::*;
 use relm4::{gtk, ComponentParts, ComponentSender};
 use sourceview5::{self, prelude::*};
 
-use crate::helpers::sourceview::create_buffer;
+use crate::errors::TemplatePosition;
+use crate::helpers::sourceview::{clear_errors, create_buffer, mark_error, ERROR_MARK};
 use crate::models::Request;
 
 #[derive(Debug, Clone)]
 pub enum RequestMsg {
     RequestChanged(Request),
     ToggleOff,
+    MarkError(TemplatePosition, String),
+    ClearErrors,
 }
 
 #[derive(Debug, Clone)]
@@ -35,6 +38,7 @@ impl RequestEditor {
 pub struct Widgets {
     request_id: usize,
     buffer: sourceview5::Buffer,
+    request_source: sourceview5::View,
     request_source_container: gtk::ScrolledWindow,
     help_container: gtk::Box,
 }
@@ -84,6 +88,17 @@ impl Component for RequestEditor {
         request_source.set_show_line_numbers(true);
         request_source.add_controller(controller);
 
+        let error_attributes = sourceview5::MarkAttributes::new();
+        error_attributes.set_icon_name("dialog-error-symbolic");
+        request_source.set_mark_attributes(ERROR_MARK, &error_attributes, 0);
+        request_source.set_show_line_marks(true);
+        // the error marks are outdated once the template is edited.
+        let error_source = request_source.clone();
+        buffer.connect_changed(move |buffer| {
+            clear_errors(buffer);
+            error_source.set_tooltip_text(None);
+        });
+
         relm4::view! {
             #[local_ref]
             root -> gtk::Box {
@@ -130,6 +145,7 @@ impl Component for RequestEditor {
             model: RequestEditor { request },
             widgets: Widgets {
                 buffer,
+                request_source,
                 request_source_container,
                 help_container,
                 request_id: 0,
@@ -137,6 +153,32 @@ impl Component for RequestEditor {
         }
     }
 
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        message: Self::Input,
+        sender: ComponentSender<Self>,
+        root: &Self::Root,
+    ) {
+        match message {
+            // the marks are displayed without reloading the template.
+            RequestMsg::MarkError(position, error) => {
+                mark_error(&widgets.buffer, position.line, position.column);
+                widgets
+                    .request_source
+                    .set_tooltip_text(Some(error.as_str()));
+            }
+            RequestMsg::ClearErrors => {
+                clear_errors(&widgets.buffer);
+                widgets.request_source.set_tooltip_text(None);
+            }
+            message => {
+                self.update(message, sender.clone(), root);
+                self.update_view(widgets, sender);
+            }
+        }
+    }
+
     fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>, _root: &Self::Root) {
         match message {
             RequestMsg::RequestChanged(request) => {
@@ -145,6 +187,7 @@ impl Component for RequestEditor {
             RequestMsg::ToggleOff => {
                 self.request = None;
             }
+            RequestMsg::MarkError(_, _) | RequestMsg::ClearErrors => {}
         }
     }
 
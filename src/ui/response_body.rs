@@ -3,79 +3,177 @@
 #![allow(deprecated)]
 
 use relm4::gtk::prelude::*;
+use relm4::gtk::{gdk_pixbuf, gio, glib};
 use relm4::prelude::*;
 use relm4::{gtk, ComponentParts, ComponentSender};
-use serde_json;
 use sourceview5;
 
-use crate::helpers::sourceview::create_buffer;
-
-fn prettify_js(payload: &str) -> Result<String, serde_json::Error> {
-    let obj: serde_json::Value = serde_json::from_str(payload)?;
-    Ok(serde_json::to_string_pretty(&obj).unwrap())
-}
+use crate::helpers::formatter;
+use crate::helpers::http::HttpResponse;
+use crate::helpers::jsonpath;
+use crate::helpers::sourceview::{create_buffer, set_language};
 
 #[derive(Debug, Clone)]
 pub enum ResponseBodyMsg {
-    ReceivingHttpResponse(String),
+    ReceivingHttpResponse(HttpResponse),
     ReceivingError(String),
+    OpenSaveBody,
+    SaveBody(String),
+    Filter(String),
+}
+
+/// Size of the binary body displayed in the hex view.
+const MAX_HEX_VIEW: usize = 64 * 1024;
+
+/// Display the bytes as `hexdump -C` does.
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut dump = String::new();
+    let displayed = &bytes[..bytes.len().min(MAX_HEX_VIEW)];
+    for (idx, chunk) in displayed.chunks(16).enumerate() {
+        let hex: Vec<String> = chunk.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let ascii: String = chunk
+            .iter()
+            .map(|byte| {
+                if byte.is_ascii_graphic() || *byte == b' ' {
+                    *byte as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        dump.push_str(format!("{:08x}  {:<47}  |{}|\n", idx * 16, hex.join(" "), ascii).as_str());
+    }
+    if bytes.len() > MAX_HEX_VIEW {
+        dump.push_str(format!("... {} more bytes\n", bytes.len() - MAX_HEX_VIEW).as_str());
+    }
+    dump
+}
+
+fn load_image(bytes: &[u8]) -> Result<gdk_pixbuf::Pixbuf, glib::Error> {
+    let stream = gio::MemoryInputStream::from_bytes(&glib::Bytes::from_owned(bytes.to_vec()));
+    gdk_pixbuf::Pixbuf::from_stream(&stream, None::<&gio::Cancellable>)
 }
 
 pub struct ResponseBody {
+    response: Option<HttpResponse>,
+    filter: String,
+    filter_entry: gtk::SearchEntry,
+    file_chooser: gtk::FileChooserNative,
+    size: gtk::Label,
+    body_stack: gtk::Stack,
+    picture: gtk::Picture,
     buffer: sourceview5::Buffer,
+    raw_buffer: sourceview5::Buffer,
+    headers: gtk::ListStore,
+    timing: gtk::Label,
+    notebook: gtk::Notebook,
 }
 
 impl ResponseBody {
-    fn log_error(&self, error: &str) {
+    fn log_error(&mut self, error: &str) {
+        self.response = None;
+        self.size.set_text("");
+        self.body_stack.set_visible_child_name("text");
+        set_language(&self.buffer, None);
         self.buffer.set_text(error);
+        self.raw_buffer.set_text("");
+        self.headers.clear();
+        self.timing.set_text("");
+        self.notebook.set_current_page(Some(0));
     }
-    fn log_response(&self, response: &str) {
-        let mut is_json = false;
-        let mut has_content = true;
-        let mut text = String::new();
-        let mut lines = response.lines();
-        loop {
-            let line = lines.next();
-            match line {
-                Some(unwrapped) => {
-                    if unwrapped.is_empty() {
-                        break;
-                    }
-                    if unwrapped.starts_with("content-type: application/json") {
-                        is_json = true;
-                    }
+
+    /// Display the body of the response, filtered by the JSONPath of the filter entry.
+    fn show_body(&self) {
+        let response = match self.response.as_ref() {
+            Some(response) => response,
+            None => return,
+        };
+        self.filter_entry.remove_css_class("error");
+        self.filter_entry.set_tooltip_text(None);
+        if response.is_binary() {
+            set_language(&self.buffer, None);
+            self.buffer.set_text(hex_dump(response.raw_body()).as_str());
+            return;
+        }
+        if !self.filter.trim().is_empty() {
+            match jsonpath::filter(response.body(), self.filter.as_str()) {
+                Ok(filtered) => {
+                    set_language(&self.buffer, Some("json"));
+                    self.buffer.set_text(filtered.as_str());
+                    return;
                 }
-                None => {
-                    has_content = false;
-                    break;
+                Err(err) => {
+                    self.filter_entry.add_css_class("error");
+                    self.filter_entry
+                        .set_tooltip_text(Some(err.to_string().as_str()));
                 }
             }
         }
-        if has_content {
-            loop {
-                let line = lines.next();
-                match line {
-                    Some(unwrapped) => {
-                        text.push_str(unwrapped);
-                        text.push('\n');
-                    }
-                    None => break,
+        let (body, language) = formatter::format(response.header("content-type"), response.body());
+        set_language(&self.buffer, language);
+        self.buffer.set_text(body.as_str());
+    }
+
+    fn log_response(&mut self, response: &HttpResponse) {
+        let kind = if response.is_binary() {
+            "binary"
+        } else {
+            "text"
+        };
+        self.size
+            .set_text(format!("{} bytes of {}", response.raw_body().len(), kind).as_str());
+        self.response = Some(response.clone());
+        self.show_body();
+
+        self.body_stack.set_visible_child_name("text");
+        if response.is_image() {
+            match load_image(response.raw_body()) {
+                Ok(pixbuf) => {
+                    self.picture.set_pixbuf(Some(&pixbuf));
+                    self.body_stack.set_visible_child_name("image");
                 }
+                Err(err) => error!("Cannot preview the image: {}", err),
             }
         }
-        let response = if is_json && has_content {
-            match prettify_js(text.as_str()) {
-                Ok(pretty) => pretty,
-                Err(_) => text,
-            }
-        } else {
-            text
-        };
 
-        self.buffer.set_text(response.as_str());
+        self.headers.clear();
+        for (key, val) in response.headers() {
+            self.headers
+                .set(&self.headers.append(), &[(0, key), (1, val)]);
+        }
+
+        self.raw_buffer.set_text(response.http_frame().as_str());
+        let timing = match response.timing() {
+            Some(timing) => timing.waterfall(),
+            None => format!("Total: {} ms", response.elapsed().as_millis()),
+        };
+        self.timing.set_text(timing.as_str());
     }
 }
 
+fn scrolled_view(buffer: &sourceview5::Buffer) -> gtk::ScrolledWindow {
+    let view = sourceview5::View::with_buffer(buffer);
+    view.set_margin_all(10);
+    view.set_hexpand(true);
+    view.set_vexpand(true);
+    let scrolled = gtk::ScrolledWindow::new();
+    scrolled.set_hexpand(true);
+    scrolled.set_vexpand(true);
+    scrolled.set_child(Some(&view));
+    scrolled
+}
+
+fn header_column(title: &str, column_id: i32) -> gtk::TreeViewColumn {
+    let cell = gtk::CellRendererText::new();
+    let column = gtk::TreeViewColumn::new();
+    column.set_title(title);
+    column.set_resizable(true);
+    column.set_sort_column_id(column_id);
+    column.pack_start(&cell, true);
+    column.add_attribute(&cell, "text", column_id);
+    column
+}
+
 pub struct Widgets {}
 
 impl Component for ResponseBody {
@@ -93,31 +191,113 @@ impl Component for ResponseBody {
     fn init(
         _request: Self::Init,
         root: Self::Root,
-        _sender: ComponentSender<Self>,
+        sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
+        let file_chooser = gtk::FileChooserNative::new(
+            Some("Save the response body"),
+            None::<&gtk::Window>,
+            gtk::FileChooserAction::Save,
+            Some("Save"),
+            Some("Cancel"),
+        );
+        let file_sender = sender.input_sender().clone();
+        file_chooser.connect_response(move |dialog, response| {
+            if response == gtk::ResponseType::Accept {
+                if let Some(path) = dialog.file().and_then(|file| file.path()) {
+                    file_sender.emit(ResponseBodyMsg::SaveBody(
+                        path.to_string_lossy().to_string(),
+                    ));
+                }
+            }
+        });
+
         let buffer = create_buffer("json");
-        let response_view = sourceview5::View::with_buffer(&buffer);
-        // response_view.set_show_line_numbers(true);
-        response_view.set_margin_all(10);
+        let picture = gtk::Picture::new();
+        picture.set_can_shrink(true);
+        let picture_scrolled = gtk::ScrolledWindow::new();
+        picture_scrolled.set_child(Some(&picture));
+        let body_stack = gtk::Stack::new();
+        body_stack.add_named(&scrolled_view(&buffer), Some("text"));
+        body_stack.add_named(&picture_scrolled, Some("image"));
+        let size = gtk::Label::new(None);
+        let filter_entry = gtk::SearchEntry::new();
+        let raw_buffer = create_buffer("rustaman-response");
+
+        let headers = gtk::ListStore::new(&[String::static_type(), String::static_type()]);
+        let headers_view = gtk::TreeView::with_model(&headers);
+        headers_view.append_column(&header_column("Name", 0));
+        headers_view.append_column(&header_column("Value", 1));
+
+        let headers_scrolled = gtk::ScrolledWindow::new();
+        headers_scrolled.set_child(Some(&headers_view));
+
+        let timing = gtk::Label::new(None);
+        timing.set_xalign(0.0);
+        timing.set_yalign(0.0);
+        timing.set_selectable(true);
+        timing.set_margin_all(10);
+        timing.add_css_class("monospace");
+
+        let notebook = gtk::Notebook::new();
+        notebook.append_page(&body_stack, Some(&gtk::Label::new(Some("Body"))));
+        notebook.append_page(&headers_scrolled, Some(&gtk::Label::new(Some("Headers"))));
+        notebook.append_page(
+            &scrolled_view(&raw_buffer),
+            Some(&gtk::Label::new(Some("Raw"))),
+        );
+        notebook.append_page(&timing, Some(&gtk::Label::new(Some("Timing"))));
 
         relm4::view! {
             #[local_ref]
             root -> gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
                 set_spacing: 5,
-                gtk::ScrolledWindow {
-                    set_hexpand: true,
-                    set_vexpand: true,
+                gtk::Box {
+                    set_spacing: 5,
+                    set_margin_start: 5,
+                    #[local_ref]
+                    size -> gtk::Label {
+                        set_xalign: 0.0,
+                    },
                     #[local_ref]
-                    response_view -> SourceView {
+                    filter_entry -> gtk::SearchEntry {
                         set_hexpand: true,
-                        set_vexpand: true,
-                    }
+                        set_placeholder_text: Some("Filter the JSON body, like $.items[*].id"),
+                        connect_search_changed[sender] => move |entry| {
+                            sender.input(ResponseBodyMsg::Filter(entry.text().to_string()));
+                        },
+                    },
+                    gtk::Button {
+                        set_label: "Save body...",
+                        set_tooltip_text: Some("Save the response body in a file, as it has been received"),
+                        connect_clicked[sender] => move |_| {
+                            sender.input(ResponseBodyMsg::OpenSaveBody);
+                        },
+                    },
+                },
+                #[local_ref]
+                notebook -> gtk::Notebook {
+                    set_hexpand: true,
+                    set_vexpand: true,
                 }
             }
         }
 
         ComponentParts {
-            model: ResponseBody { buffer },
+            model: ResponseBody {
+                response: None,
+                filter: String::new(),
+                filter_entry,
+                file_chooser,
+                size,
+                body_stack,
+                picture,
+                buffer,
+                raw_buffer,
+                headers,
+                timing,
+                notebook,
+            },
             widgets: Widgets {},
         }
     }
@@ -125,10 +305,23 @@ impl Component for ResponseBody {
     fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>, _root: &Self::Root) {
         debug!("Updating response body");
         match message {
-            ResponseBodyMsg::ReceivingHttpResponse(response) => {
-                self.log_response(response.as_str())
-            }
+            ResponseBodyMsg::ReceivingHttpResponse(response) => self.log_response(&response),
             ResponseBodyMsg::ReceivingError(error) => self.log_error(error.as_str()),
+            ResponseBodyMsg::OpenSaveBody => self.file_chooser.show(),
+            ResponseBodyMsg::SaveBody(filepath) => {
+                let body = self
+                    .response
+                    .as_ref()
+                    .map(|response| response.raw_body())
+                    .unwrap_or_default();
+                if let Err(err) = std::fs::write(filepath.as_str(), body) {
+                    self.log_error(format!("Cannot save the body: {}", err).as_str());
+                }
+            }
+            ResponseBodyMsg::Filter(filter) => {
+                self.filter = filter;
+                self.show_body();
+            }
         }
     }
 
@@ -9,11 +9,20 @@ use relm4::prelude::*;
 use relm4::{gtk, ComponentParts, ComponentSender};
 use relm4_icons::icon_names;
 
+use crate::converters::command::CommandFormat;
+use crate::converters::snippet::SnippetLanguage;
 use crate::models::Request;
 use crate::ui::menu_item::MenuItemOutput;
 
 use super::menu_item::{MenuItem, MenuMode};
 
+/// What is exported by the export file chooser.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportKind {
+    HttpFile,
+    Har,
+}
+
 #[derive(Debug, Clone)]
 pub enum SideBarMsg {
     NewRequest,
@@ -26,20 +35,38 @@ pub enum SideBarMsg {
     RenameRequest(usize, String),
     RequestRenamed(usize, String),
     RequestDeleted(usize),
+    ImportCurl,
+    OpenImportFile,
+    ImportFile(String),
+    OpenExportFile(ExportKind),
+    ExportFile(String),
+    CopyAs(usize, CommandFormat),
+    CopySnippet(usize, SnippetLanguage, bool),
 }
 
 #[derive(Debug, Clone)]
 pub enum SideBarOutput {
     NewRequest,
+    ImportCurl(String),
+    ImportFile(String),
+    ExportFile(String),
+    ExportHar(String),
     TogglingRequest(usize),
     DeleteRequest(usize),
     RenameRequest(usize, String),
+    CopyAs(usize, CommandFormat),
+    CopySnippet(usize, SnippetLanguage, bool),
     ToggleOff,
 }
 
 pub struct SideBar {
     menu_items: FactoryVecDeque<MenuItem>,
     search_entry: gtk::SearchEntry,
+    import_popover: gtk::Popover,
+    curl_buffer: gtk::TextBuffer,
+    file_chooser: gtk::FileChooserNative,
+    export_file_chooser: gtk::FileChooserNative,
+    export_kind: ExportKind,
 }
 
 impl SideBar {}
@@ -78,6 +105,12 @@ impl Component for SideBar {
                     MenuItemOutput::RenameRequest(request_id, name) => {
                         SideBarMsg::RenameRequest(request_id, name)
                     }
+                    MenuItemOutput::CopyAs(request_id, format) => {
+                        SideBarMsg::CopyAs(request_id, format)
+                    }
+                    MenuItemOutput::CopySnippet(request_id, language, keep_placeholders) => {
+                        SideBarMsg::CopySnippet(request_id, language, keep_placeholders)
+                    }
                 });
 
         for request in requests {
@@ -98,6 +131,90 @@ impl Component for SideBar {
 
         let new_request_btn = gtk::Button::new();
 
+        let file_chooser = gtk::FileChooserNative::new(
+            Some("Import a file"),
+            None::<&gtk::Window>,
+            gtk::FileChooserAction::Open,
+            Some("Import"),
+            Some("Cancel"),
+        );
+        let file_filter = gtk::FileFilter::new();
+        file_filter.set_name(Some("Postman, OpenAPI, HAR and .http files"));
+        file_filter.add_pattern("*.json");
+        file_filter.add_pattern("*.yaml");
+        file_filter.add_pattern("*.yml");
+        file_filter.add_pattern("*.http");
+        file_filter.add_pattern("*.rest");
+        file_filter.add_pattern("*.har");
+        file_chooser.add_filter(&file_filter);
+        let file_sender = sender.input_sender().clone();
+        file_chooser.connect_response(move |dialog, response| {
+            if response == gtk::ResponseType::Accept {
+                if let Some(path) = dialog.file().and_then(|file| file.path()) {
+                    file_sender.emit(SideBarMsg::ImportFile(path.to_string_lossy().to_string()));
+                }
+            }
+        });
+
+        let export_file_chooser = gtk::FileChooserNative::new(
+            Some("Export the requests"),
+            None::<&gtk::Window>,
+            gtk::FileChooserAction::Save,
+            Some("Export"),
+            Some("Cancel"),
+        );
+        let export_sender = sender.input_sender().clone();
+        export_file_chooser.connect_response(move |dialog, response| {
+            if response == gtk::ResponseType::Accept {
+                if let Some(path) = dialog.file().and_then(|file| file.path()) {
+                    export_sender.emit(SideBarMsg::ExportFile(path.to_string_lossy().to_string()));
+                }
+            }
+        });
+
+        let import_popover = gtk::Popover::new();
+        let curl_view = gtk::TextView::new();
+        let curl_buffer = curl_view.buffer();
+        relm4::view! {
+            #[local_ref]
+            import_popover -> gtk::Popover {
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_spacing: 5,
+                    gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_label: "Paste a curl command",
+                    },
+                    gtk::ScrolledWindow {
+                        set_min_content_width: 400,
+                        set_min_content_height: 150,
+                        #[local_ref]
+                        curl_view -> gtk::TextView {
+                            set_monospace: true,
+                            set_wrap_mode: gtk::WrapMode::Char,
+                        }
+                    },
+                    gtk::Button {
+                        set_label: "Import",
+                        connect_clicked => SideBarMsg::ImportCurl,
+                    },
+                    gtk::Separator {},
+                    gtk::Button {
+                        set_label: "Import a file...",
+                        connect_clicked => SideBarMsg::OpenImportFile,
+                    },
+                    gtk::Button {
+                        set_label: "Export as .http file...",
+                        connect_clicked => SideBarMsg::OpenExportFile(ExportKind::HttpFile),
+                    },
+                    gtk::Button {
+                        set_label: "Export the traffic as HAR...",
+                        connect_clicked => SideBarMsg::OpenExportFile(ExportKind::Har),
+                    }
+                }
+            }
+        }
+
         relm4::view! {
             #[local_ref]
             root -> gtk::Box {
@@ -112,6 +229,11 @@ impl Component for SideBar {
                         set_icon_name: icon_names::DOCUMENT_ADD_REGULAR,
                         connect_clicked => SideBarMsg::NewRequest
                     },
+                    gtk::MenuButton {
+                        set_icon_name: "document-open-symbolic",
+                        set_tooltip_text: Some("Import and export"),
+                        set_popover: Some(&import_popover),
+                    },
                     #[local_ref]
                     search_entry -> gtk::SearchEntry {
                         set_vexpand: false,
@@ -136,6 +258,11 @@ impl Component for SideBar {
             model: SideBar {
                 menu_items,
                 search_entry,
+                import_popover,
+                curl_buffer,
+                file_chooser,
+                export_file_chooser,
+                export_kind: ExportKind::HttpFile,
             },
             widgets: Widgets {},
         }
@@ -198,6 +325,49 @@ impl Component for SideBar {
                     }
                 }
             }
+            SideBarMsg::ImportCurl => {
+                let (start_iter, end_iter) = self.curl_buffer.bounds();
+                let command = self.curl_buffer.text(&start_iter, &end_iter, true);
+                self.curl_buffer.set_text("");
+                self.import_popover.popdown();
+                sender
+                    .output_sender()
+                    .emit(SideBarOutput::ImportCurl(command.into()));
+            }
+            SideBarMsg::OpenImportFile => {
+                self.import_popover.popdown();
+                self.file_chooser.show();
+            }
+            SideBarMsg::ImportFile(filepath) => sender
+                .output_sender()
+                .emit(SideBarOutput::ImportFile(filepath.clone())),
+            SideBarMsg::OpenExportFile(kind) => {
+                self.import_popover.popdown();
+                self.export_kind = *kind;
+                self.export_file_chooser.set_current_name(match kind {
+                    ExportKind::HttpFile => "requests.http",
+                    ExportKind::Har => "traffic.har",
+                });
+                self.export_file_chooser.show();
+            }
+            SideBarMsg::ExportFile(filepath) => match self.export_kind {
+                ExportKind::HttpFile => sender
+                    .output_sender()
+                    .emit(SideBarOutput::ExportFile(filepath.clone())),
+                ExportKind::Har => sender
+                    .output_sender()
+                    .emit(SideBarOutput::ExportHar(filepath.clone())),
+            },
+            SideBarMsg::CopyAs(request_id, format) => sender
+                .output_sender()
+                .emit(SideBarOutput::CopyAs(*request_id, *format)),
+            SideBarMsg::CopySnippet(request_id, language, keep_placeholders) => {
+                sender.output_sender().emit(SideBarOutput::CopySnippet(
+                    *request_id,
+                    *language,
+                    *keep_placeholders,
+                ))
+            }
             SideBarMsg::DeleteRequest(request_id) => sender
                 .output_sender()
                 .emit(SideBarOutput::DeleteRequest(*request_id)),
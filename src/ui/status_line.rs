@@ -8,9 +8,21 @@ use relm4::gtk::prelude::*;
 use relm4::prelude::*;
 use relm4::{gtk, ComponentParts, ComponentSender};
 
+use crate::helpers::expect::Assertion;
+
 #[derive(Debug, Clone)]
 pub enum StatusLineMsg {
-    ReceivingHttpResponse(String, Duration),
+    RunningHttpRequest,
+    /// The response frame, its duration and its timing waterfall.
+    ReceivingHttpResponse(String, Duration, Option<String>),
+    ReceivingAssertions(Vec<Assertion>),
+    HttpRequestDone,
+    HttpRequestCancelled,
+}
+
+#[derive(Debug, Clone)]
+pub enum StatusLineOutput {
+    CancelHttpRequest,
 }
 
 fn build_markup_for_status(status: &str) -> String {
@@ -36,9 +48,26 @@ fn build_markup_for_elapsed(elapsed: u64) -> String {
     fmt
 }
 
+fn build_markup_for_assertions(passed: usize, failed: usize) -> String {
+    let (color, text) = if failed > 0 {
+        ("#B40404", format!("{} failed", failed))
+    } else {
+        ("#088A29", format!("{} passed", passed))
+    };
+    let fmt = format!(
+        r#"<span face="monospace" background="{}" size="large"> {} </span>"#,
+        color, text
+    );
+    fmt
+}
+
 pub struct StatusLine {
     status_line: String,
     elapsed: Option<Duration>,
+    timing: Option<String>,
+    running: bool,
+    passed: usize,
+    failed: usize,
 }
 
 impl StatusLine {}
@@ -48,12 +77,17 @@ impl StatusLine {}
 pub struct Widgets {
     status_line: gtk::Label,
     elapsed: gtk::Label,
+    timing_btn: gtk::MenuButton,
+    timing: gtk::Label,
+    assertions: gtk::Label,
+    spinner: gtk::Spinner,
+    cancel_btn: gtk::Button,
 }
 
 impl Component for StatusLine {
     type Init = ();
     type Input = StatusLineMsg;
-    type Output = ();
+    type Output = StatusLineOutput;
     type CommandOutput = ();
     type Widgets = Widgets;
     type Root = gtk::Box;
@@ -65,10 +99,19 @@ impl Component for StatusLine {
     fn init(
         _request: Self::Init,
         root: Self::Root,
-        _sender: ComponentSender<Self>,
+        sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
         let status_line = gtk::Label::new(None);
         let elapsed = gtk::Label::new(None);
+        let timing = gtk::Label::new(None);
+        timing.set_selectable(true);
+        timing.add_css_class("monospace");
+        let timing_popover = gtk::Popover::new();
+        timing_popover.set_child(Some(&timing));
+        let timing_btn = gtk::MenuButton::new();
+        let assertions = gtk::Label::new(None);
+        let spinner = gtk::Spinner::new();
+        let cancel_btn = gtk::Button::new();
 
         relm4::view! {
             #[local_ref]
@@ -82,33 +125,91 @@ impl Component for StatusLine {
                 elapsed -> gtk::Label{
                     set_margin_start: 5,
                 },
+                #[local_ref]
+                timing_btn -> gtk::MenuButton {
+                    set_label: "Timing",
+                    set_tooltip_text: Some("The phases of the request"),
+                    set_popover: Some(&timing_popover),
+                },
+                #[local_ref]
+                assertions -> gtk::Label{
+                    set_margin_start: 5,
+                },
+                #[local_ref]
+                spinner -> gtk::Spinner {
+                    set_margin_start: 5,
+                },
+                #[local_ref]
+                cancel_btn -> gtk::Button {
+                    set_label: "Cancel",
+                    set_tooltip_text: Some("Escape"),
+                    connect_clicked[sender] => move |_| {
+                        // the window may be closing, there is nothing to cancel.
+                        let _ = sender.output(StatusLineOutput::CancelHttpRequest);
+                    },
+                },
             }
         }
+        cancel_btn.hide();
+        timing_btn.hide();
 
         ComponentParts {
             model: StatusLine {
                 status_line: "".to_string(),
                 elapsed: None,
+                timing: None,
+                running: false,
+                passed: 0,
+                failed: 0,
             },
             widgets: Widgets {
                 status_line,
                 elapsed,
+                timing_btn,
+                timing,
+                assertions,
+                spinner,
+                cancel_btn,
             },
         }
     }
 
     fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>, _root: &Self::Root) {
         match message {
-            StatusLineMsg::ReceivingHttpResponse(response, elapsed) => {
+            StatusLineMsg::RunningHttpRequest => {
+                self.running = true;
+                self.passed = 0;
+                self.failed = 0;
+            }
+            StatusLineMsg::ReceivingHttpResponse(response, elapsed, timing) => {
+                self.timing = timing;
                 let first_line = response.lines().next().unwrap_or("").to_string();
                 let v: Vec<&str> = first_line.splitn(2, ' ').collect();
                 self.status_line = v.last().unwrap_or(&"").to_string();
                 self.elapsed = Some(elapsed)
             }
+            StatusLineMsg::ReceivingAssertions(assertions) => {
+                for assertion in assertions.iter() {
+                    if assertion.passed() {
+                        self.passed += 1;
+                    } else {
+                        self.failed += 1;
+                    }
+                }
+            }
+            StatusLineMsg::HttpRequestDone => self.running = false,
+            StatusLineMsg::HttpRequestCancelled => {
+                self.running = false;
+                self.status_line = "Cancelled".to_string();
+                self.elapsed = None;
+                self.timing = None;
+            }
         }
     }
 
     fn update_view(&self, widgets: &mut Self::Widgets, _sender: ComponentSender<Self>) {
+        widgets.spinner.set_spinning(self.running);
+        widgets.cancel_btn.set_visible(self.running);
         widgets
             .status_line
             .set_markup(build_markup_for_status(self.status_line.as_str()).as_str());
@@ -118,6 +219,20 @@ impl Component for StatusLine {
             widgets
                 .elapsed
                 .set_markup(build_markup_for_elapsed(ms).as_str());
+        } else {
+            widgets.elapsed.set_markup("");
+        }
+        widgets.elapsed.set_tooltip_text(self.timing.as_deref());
+        widgets.timing_btn.set_visible(self.timing.is_some());
+        widgets
+            .timing
+            .set_text(self.timing.as_deref().unwrap_or_default());
+        if self.passed + self.failed > 0 {
+            widgets
+                .assertions
+                .set_markup(build_markup_for_assertions(self.passed, self.failed).as_str());
+        } else {
+            widgets.assertions.set_markup("");
         }
     }
 }
@@ -7,21 +7,35 @@ use relm4::prelude::*;
 use relm4::{gtk, ComponentParts, ComponentSender};
 use sourceview5;
 
+use crate::converters::har::Exchange;
+use crate::helpers::expect::Assertion;
+use crate::helpers::http::HttpResponse;
+use crate::helpers::httpparser::HttpRequest;
 use crate::helpers::sourceview::create_buffer;
 
 #[derive(Debug, Clone)]
 pub enum TrafficLogMsg {
-    SendingHttpRequest(String),
+    SendingHttpRequest(HttpRequest),
     RequestSent(usize),
-    ReceivingHttpResponse(String),
+    ReceivingHttpResponse(HttpResponse),
     ReceivingError(String),
+    Asserted(Vec<Assertion>),
 }
 
+/// Number of exchanges kept for the export, the oldest are dropped.
+const MAX_EXCHANGES: usize = 100;
+
 pub struct TrafficLog {
     buffer: sourceview5::Buffer,
+    history: Vec<Exchange>,
 }
 
 impl TrafficLog {
+    /// The exchanges logged, to be exported.
+    pub fn history(&self) -> &[Exchange] {
+        self.history.as_slice()
+    }
+
     fn log(&self, msg: &str) {
         let start_iter = self.buffer.start_iter();
         let end_iter = self.buffer.end_iter();
@@ -72,7 +86,10 @@ impl Component for TrafficLog {
         }
 
         ComponentParts {
-            model: TrafficLog { buffer },
+            model: TrafficLog {
+                buffer,
+                history: Vec::new(),
+            },
             widgets: Widgets {},
         }
     }
@@ -83,24 +100,48 @@ impl Component for TrafficLog {
             TrafficLogMsg::SendingHttpRequest(request) => {
                 self.log("```http");
                 self.log(">>> New request");
-                self.log(request.as_str());
+                self.log(request.http_frame());
+                self.history.push(Exchange::new(request));
+                if self.history.len() > MAX_EXCHANGES {
+                    let overflow = self.history.len() - MAX_EXCHANGES;
+                    self.history.drain(..overflow);
+                }
             }
             TrafficLogMsg::RequestSent(request_length) => {
                 self.log(format!(">>> End of request ({} bytes sent)", request_length).as_str());
             }
             TrafficLogMsg::ReceivingHttpResponse(response) => {
+                let frame = response.http_frame();
                 self.log("<<< Response");
-                self.log(response.as_str());
-                self.log(
-                    format!("<<< End of response ({} bytes received)", response.len()).as_str(),
-                );
+                self.log(frame.as_str());
+                self.log(format!("<<< End of response ({} bytes received)", frame.len()).as_str());
+                if let Some(timing) = response.timing() {
+                    self.log(timing.waterfall().as_str());
+                }
                 self.log("```");
+                if let Some(exchange) = self.history.last_mut() {
+                    exchange.response = Some(response);
+                }
             }
             TrafficLogMsg::ReceivingError(error) => {
                 self.log("<<< Response error");
                 self.log(error.as_str());
                 self.log("<<< End of response");
                 self.log("```");
+                if let Some(exchange) = self.history.last_mut() {
+                    if exchange.response.is_none() {
+                        exchange.error = Some(error);
+                    }
+                }
+            }
+            TrafficLogMsg::Asserted(assertions) => {
+                for assertion in assertions.iter() {
+                    match assertion.failure() {
+                        None => self.log(format!("Expect {}: passed", assertion.expect()).as_str()),
+                        Some(failure) => self
+                            .log(format!("! Expect {}: {}", assertion.expect(), failure).as_str()),
+                    }
+                }
             }
         }
     }
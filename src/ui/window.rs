@@ -1,29 +1,32 @@
 // Don't show GTK 4.10 deprecations.
 // We can't replace them without raising the GTK requirement to 4.10.
 #![allow(deprecated)]
-use std::time::SystemTime;
-
 use relm4::component::Connector;
 use relm4::gtk::prelude::*;
 use relm4::prelude::*;
 use relm4::{gtk, ComponentParts, ComponentSender};
+use tokio::task::JoinHandle;
 
-use reqwest;
-use reqwest::header::HOST;
-use url::Url;
-
-use crate::helpers::httpparser;
+use crate::converters::command::{self, CommandFormat};
+use crate::converters::snippet::{self, SnippetLanguage};
+use crate::converters::{self, curl, har, http_file, ImportedCollection, ImportedRequest};
+use crate::errors::TemplatePosition;
+use crate::helpers::http::HttpResponse;
+use crate::helpers::path;
+use crate::helpers::runner::{self, RunEvent};
+use crate::ui::diff_view::{DiffMsg, DiffView};
 use crate::ui::environments::{EnvironmentsMsg, EnvironmentsOutput};
+use crate::ui::history::{HistoryMsg, HistoryOutput, HistoryPanel};
 use crate::ui::request_editor::{RequestMsg, RequestOutput};
 use crate::ui::response_body::{ResponseBody, ResponseBodyMsg};
 use crate::ui::sidebar::SideBarOutput;
 use crate::ui::traffic_log::{TrafficLog, TrafficLogMsg};
 
-use super::super::models::{Environment, Workspace, USER_AGENT};
+use super::super::models::{Environment, HistoryEntry, Workspace};
 use super::environments::EnvironmentsTabs;
 use super::request_editor::RequestEditor;
 use super::sidebar::{SideBar, SideBarMsg};
-use super::status_line::{StatusLine, StatusLineMsg};
+use super::status_line::{StatusLine, StatusLineMsg, StatusLineOutput};
 
 #[derive(Debug, Clone)]
 pub enum AppMsg {
@@ -38,6 +41,22 @@ pub enum AppMsg {
     RenameEnvironment(usize, String),
     DeleteEnvironment(usize),
     SaveHttpRequest(usize, String),
+    CancelHttpRequest,
+    ImportCurl(String),
+    ImportFile(String),
+    ExportFile(String),
+    ExportHar(String),
+    CopyAs(usize, CommandFormat),
+    CopySnippet(usize, SnippetLanguage, bool),
+    OpenHistory(usize),
+    CompareHistory(usize, usize),
+}
+
+#[derive(Debug)]
+pub enum AppCmd {
+    Running(usize, RunEvent),
+    /// The error of the run, and its position in the template.
+    Done(usize, Option<String>, Option<TemplatePosition>),
 }
 
 pub struct App {
@@ -47,7 +66,15 @@ pub struct App {
     environments: Controller<EnvironmentsTabs>,
     response_body: Connector<ResponseBody>,
     traffic_log: Connector<TrafficLog>,
-    status_line: Connector<StatusLine>,
+    history: Controller<HistoryPanel>,
+    diff_view: Controller<DiffView>,
+    response_tabs: gtk::Notebook,
+    status_line: Controller<StatusLine>,
+    running: Option<JoinHandle<()>>,
+    run_id: usize,
+    awaiting_response: bool,
+    /// The request and the environment of the run, to store the responses.
+    run_target: Option<(usize, Option<usize>)>,
 }
 
 impl App {
@@ -61,7 +88,7 @@ impl App {
         }
         environ
     }
-    fn refresh_request(&mut self) -> Vec<String> {
+    fn refresh_request(&mut self) -> String {
         let mut template = String::new();
         if let Some(request_id) = self.request_editor.model().request_id() {
             let request_editor = self.request_editor.widgets();
@@ -69,16 +96,132 @@ impl App {
             self.workspace
                 .set_request_template(request_id, template.as_str());
         }
-        httpparser::split_template(template.as_str())
+        template
+    }
+
+    /// The template of the request, including the unsaved changes of the editor.
+    fn request_template(&mut self, request_id: usize) -> String {
+        if self.request_editor.model().request_id() == Some(request_id) {
+            self.refresh_request();
+        }
+        self.workspace
+            .request(request_id)
+            .map(|request| request.template().to_string())
+            .unwrap_or_default()
+    }
+
+    fn history_label(&self, entry: &HistoryEntry) -> String {
+        let environment = entry
+            .environment_id()
+            .and_then(|environment_id| self.workspace.environment(environment_id))
+            .map(|environment| environment.name())
+            .unwrap_or("");
+        let truncated = if entry.is_truncated() {
+            " (truncated)"
+        } else {
+            ""
+        };
+        format!(
+            "{} {} {} {}ms {}{}",
+            har::iso8601(entry.timestamp()),
+            entry.status(),
+            entry.reason(),
+            entry.duration().as_millis(),
+            environment,
+            truncated
+        )
+    }
+
+    /// The response stored in the history of the current request.
+    fn history_entry(&self, index: usize) -> Option<&HistoryEntry> {
+        self.request_editor
+            .model()
+            .request_id()
+            .and_then(|request_id| self.workspace.history(request_id).get(index))
+    }
+
+    /// Show the responses of the request, the latest first.
+    fn show_history(&self, request_id: usize) {
+        let entries = self
+            .workspace
+            .history(request_id)
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, entry)| (index, self.history_label(entry)))
+            .collect();
+        self.history.emit(HistoryMsg::Show(entries));
+    }
+
+    /// Add the imported requests to the workspace, and open the last one.
+    fn import_requests(&mut self, imported: Vec<ImportedRequest>) {
+        let mut request_id = None;
+        for imported in imported.iter() {
+            let request = self
+                .workspace
+                .import_request(imported.name.as_str(), imported.template.as_str());
+            request_id = Some(request.id());
+            self.sidebar
+                .emit(SideBarMsg::RegisterRequest(request.clone()));
+        }
+        if let Some(request_id) = request_id {
+            self.sidebar
+                .emit(SideBarMsg::TogglingRequest(request_id, true));
+        }
+    }
+
+    fn import_collection(&mut self, imported: ImportedCollection) {
+        for environment in imported.environments.iter() {
+            let existing = self
+                .workspace
+                .environment_by_name(environment.name.as_str())
+                .map(|environment| environment.id());
+            if let Some(env_id) = existing {
+                // the editor may hold changes not saved yet.
+                if let Some(payload) = self.environments.model().environment_payload(env_id) {
+                    self.workspace.set_environ_payload(env_id, payload.as_str());
+                }
+            }
+            let environment = self
+                .workspace
+                .import_environment(environment.name.as_str(), environment.payload.as_str());
+            if existing.is_some() {
+                self.environments
+                    .emit(EnvironmentsMsg::EnvironmentUpdated(environment.clone()));
+            } else {
+                self.environments
+                    .emit(EnvironmentsMsg::EnvironmentCreated(environment.clone()));
+            }
+        }
+        self.import_requests(imported.requests);
+    }
+
+    /// Abort the running requests, return true if there were running.
+    ///
+    /// A run that has finished is not cancelled, its `Done` event is pending.
+    fn cancel_http_request(&mut self) -> bool {
+        match self.running.take() {
+            Some(handle) if !handle.is_finished() => {
+                handle.abort();
+                if self.awaiting_response {
+                    self.awaiting_response = false;
+                    self.traffic_log
+                        .emit(TrafficLogMsg::ReceivingError("Cancelled".to_string()));
+                }
+                true
+            }
+            _ => false,
+        }
     }
 }
+
 pub struct Widgets {}
 
 impl Component for App {
     type Init = Workspace;
     type Input = AppMsg;
     type Output = ();
-    type CommandOutput = ();
+    type CommandOutput = AppCmd;
     type Widgets = Widgets;
     type Root = gtk::ApplicationWindow;
 
@@ -106,6 +249,14 @@ impl Component for App {
                     AppMsg::RenameRequest(request_id, name)
                 }
                 SideBarOutput::ToggleOff => AppMsg::ToggleOff,
+                SideBarOutput::ImportCurl(command) => AppMsg::ImportCurl(command),
+                SideBarOutput::ImportFile(filepath) => AppMsg::ImportFile(filepath),
+                SideBarOutput::ExportFile(filepath) => AppMsg::ExportFile(filepath),
+                SideBarOutput::ExportHar(filepath) => AppMsg::ExportHar(filepath),
+                SideBarOutput::CopyAs(request_id, format) => AppMsg::CopyAs(request_id, format),
+                SideBarOutput::CopySnippet(request_id, language, keep_placeholders) => {
+                    AppMsg::CopySnippet(request_id, language, keep_placeholders)
+                }
             });
 
         let request_editor =
@@ -131,7 +282,18 @@ impl Component for App {
 
         let response_body = ResponseBody::builder().launch(());
         let traffic_log = TrafficLog::builder().launch(());
-        let status_line = StatusLine::builder().launch(());
+        let history = HistoryPanel::builder()
+            .launch(())
+            .forward(sender.input_sender(), |msg| match msg {
+                HistoryOutput::Open(index) => AppMsg::OpenHistory(index),
+                HistoryOutput::Compare(older, newer) => AppMsg::CompareHistory(older, newer),
+            });
+        let diff_view = DiffView::builder().launch(()).detach();
+        let status_line = StatusLine::builder()
+            .launch(())
+            .forward(sender.input_sender(), |msg| match msg {
+                StatusLineOutput::CancelHttpRequest => AppMsg::CancelHttpRequest,
+            });
         let status_line_widget = status_line.widget();
         relm4::view! {
             request_box = gtk::Box {
@@ -145,6 +307,14 @@ impl Component for App {
             }
         }
 
+        let response_tabs = gtk::Notebook::new();
+        response_tabs.append_page(
+            traffic_log.widget(),
+            Some(&gtk::Label::new(Some("Traffic"))),
+        );
+        response_tabs.append_page(history.widget(), Some(&gtk::Label::new(Some("History"))));
+        response_tabs.append_page(diff_view.widget(), Some(&gtk::Label::new(Some("Diff"))));
+
         relm4::view! {
             response_box = gtk::Box {
                 set_orientation: gtk::Orientation::Vertical,
@@ -158,7 +328,7 @@ impl Component for App {
                 },
                 gtk::Paned::new(gtk::Orientation::Vertical) {
                     set_start_child: Some(response_body.widget()),
-                    set_end_child: Some(traffic_log.widget()),
+                    set_end_child: Some(&response_tabs),
                 }
             }
         }
@@ -180,6 +350,10 @@ impl Component for App {
         let root_sender = sender.input_sender().clone();
         let controller = gtk::EventControllerKey::new();
         controller.connect_key_pressed(move |_evt, key, _code, mask| {
+            if key == gtk::gdk::Key::Escape {
+                root_sender.emit(AppMsg::CancelHttpRequest);
+                return false.into();
+            }
             if mask != gtk::gdk::ModifierType::CONTROL_MASK {
                 return false.into();
             }
@@ -218,14 +392,21 @@ impl Component for App {
                 request_editor,
                 environments,
                 traffic_log,
+                history,
+                diff_view,
+                response_tabs,
                 status_line,
                 response_body,
+                running: None,
+                run_id: 0,
+                awaiting_response: false,
+                run_target: None,
             },
             widgets: Widgets {},
         }
     }
 
-    fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>, _root: &Self::Root) {
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>, root: &Self::Root) {
         match message {
             AppMsg::NewRequest => {
                 debug!("Creating new request");
@@ -239,9 +420,11 @@ impl Component for App {
                     self.request_editor
                         .emit(RequestMsg::RequestChanged(request.clone()));
                 }
+                self.show_history(request_id);
             }
             AppMsg::ToggleOff => {
                 self.request_editor.emit(RequestMsg::ToggleOff);
+                self.history.emit(HistoryMsg::Clear);
             }
             AppMsg::RenameRequest(request_id, name) => {
                 self.workspace.set_request_name(request_id, name.as_str());
@@ -280,93 +463,196 @@ impl Component for App {
                     .emit(EnvironmentsMsg::EnvironmentDeleted(environment_id));
             }
             AppMsg::RunHttpRequest => {
+                self.cancel_http_request();
                 let environ = self.refresh_environment();
-                let req_templates = self.refresh_request();
+                let template = self.refresh_request();
+                self.request_editor.emit(RequestMsg::ClearErrors);
                 self.workspace.safe_sync();
-                for req_template in req_templates.iter() {
-                    debug!("Processing {:?}", req_template);
-                    let request_parsed = httpparser::load_template(req_template.as_str(), &environ);
-                    if let Err(rustaman_err) = request_parsed {
-                        let error = format!("{:?}", rustaman_err);
-                        self.response_body
-                            .emit(ResponseBodyMsg::ReceivingError(error));
-                        return;
-                    }
 
-                    let httpreq = request_parsed.unwrap();
-                    let mut cbuilder =
-                        reqwest::blocking::ClientBuilder::new().user_agent(USER_AGENT);
-                    if !httpreq.verify_cert() {
-                        cbuilder = cbuilder.danger_accept_invalid_certs(true);
-                    }
-                    let cli = cbuilder.build().unwrap();
-                    let mut req = cli.request(httpreq.method(), httpreq.url());
-                    let mut has_host: bool = false;
-                    for (key, val) in httpreq.headers() {
-                        has_host = has_host || key.to_lowercase() == "host";
-                        req = req.header(key, val);
+                self.run_id += 1;
+                let run_id = self.run_id;
+                self.run_target = self
+                    .request_editor
+                    .model()
+                    .request_id()
+                    .map(|request_id| (request_id, self.environments.model().environment_id()));
+                self.status_line.emit(StatusLineMsg::RunningHttpRequest);
+                let cmd_sender = sender.command_sender().clone();
+                let handle = relm4::spawn(async move {
+                    let result = runner::run(template, environ, |event| {
+                        cmd_sender.emit(AppCmd::Running(run_id, event))
+                    })
+                    .await;
+                    let position = result.as_ref().err().and_then(|err| err.position());
+                    cmd_sender.emit(AppCmd::Done(
+                        run_id,
+                        result.err().map(|err| err.to_string()),
+                        position,
+                    ));
+                });
+                self.running = Some(handle);
+            }
+            AppMsg::ImportCurl(command) => match curl::import(command.as_str()) {
+                Ok(imported) => self.import_requests(vec![imported]),
+                Err(err) => self
+                    .response_body
+                    .emit(ResponseBodyMsg::ReceivingError(err.to_string())),
+            },
+            AppMsg::ImportFile(filepath) => match converters::import_file(filepath.as_str()) {
+                Ok(imported) => self.import_collection(imported),
+                Err(err) => self
+                    .response_body
+                    .emit(ResponseBodyMsg::ReceivingError(err.to_string())),
+            },
+            AppMsg::ExportFile(filepath) => {
+                self.refresh_request();
+                let environ = self.refresh_environment();
+                self.workspace.safe_sync();
+                let result = http_file::export(&self.workspace, &environ).and_then(|content| {
+                    path::write_file(filepath.as_str(), content.as_str())?;
+                    Ok(())
+                });
+                if let Err(err) = result {
+                    self.response_body
+                        .emit(ResponseBodyMsg::ReceivingError(err.to_string()));
+                }
+            }
+            AppMsg::ExportHar(filepath) => {
+                let content = har::export(self.traffic_log.model().history());
+                if let Err(err) = path::write_file(filepath.as_str(), content.as_str()) {
+                    self.response_body
+                        .emit(ResponseBodyMsg::ReceivingError(err.to_string()));
+                }
+            }
+            AppMsg::CopyAs(request_id, format) => {
+                let environ = self.refresh_environment();
+                let template = self.request_template(request_id);
+                match command::export_template(template.as_str(), &environ, format) {
+                    Ok(command) => {
+                        info!("Copying request {} as {:?}", request_id, format);
+                        root.clipboard().set_text(command.as_str());
                     }
-
-                    if !has_host {
-                        // Parse the URL with `reqwest`
-                        let parsed_url_result = Url::parse(httpreq.url());
-                        if let Ok(parsed_url) = parsed_url_result {
-                            // Extract the host from the URL
-                            let host = parsed_url.host_str().unwrap_or("No host found");
-                            req = req.header(HOST, host);
-                        }
+                    Err(err) => self
+                        .response_body
+                        .emit(ResponseBodyMsg::ReceivingError(err.to_string())),
+                }
+            }
+            AppMsg::CopySnippet(request_id, language, keep_placeholders) => {
+                let environ = self.refresh_environment();
+                let template = self.request_template(request_id);
+                match snippet::generate_template(
+                    template.as_str(),
+                    &environ,
+                    language,
+                    keep_placeholders,
+                ) {
+                    Ok(code) => {
+                        info!("Copying request {} as {:?} snippet", request_id, language);
+                        root.clipboard().set_text(code.as_str());
                     }
-
-                    if let Some(body) = httpreq.body() {
-                        req = req.body(body.to_string());
+                    Err(err) => self
+                        .response_body
+                        .emit(ResponseBodyMsg::ReceivingError(err.to_string())),
+                }
+            }
+            AppMsg::OpenHistory(index) => {
+                if let Some(entry) = self.history_entry(index) {
+                    let response = HttpResponse::from(entry);
+                    self.status_line.emit(StatusLineMsg::ReceivingHttpResponse(
+                        response.http_frame(),
+                        response.elapsed(),
+                        None,
+                    ));
+                    self.response_body
+                        .emit(ResponseBodyMsg::ReceivingHttpResponse(response));
+                }
+            }
+            AppMsg::CompareHistory(older, newer) => {
+                if let (Some(left), Some(right)) =
+                    (self.history_entry(older), self.history_entry(newer))
+                {
+                    self.diff_view.emit(DiffMsg::Compare(
+                        self.history_label(left),
+                        HttpResponse::from(left),
+                        self.history_label(right),
+                        HttpResponse::from(right),
+                    ));
+                    if let Some(page) = self.response_tabs.page_num(self.diff_view.widget()) {
+                        self.response_tabs.set_current_page(Some(page));
                     }
+                }
+            }
+            AppMsg::CancelHttpRequest => {
+                if self.cancel_http_request() {
+                    info!("Http request cancelled");
+                    self.status_line.emit(StatusLineMsg::HttpRequestCancelled);
+                }
+            }
+        }
+    }
 
-                    let time = SystemTime::now();
-
-                    let obfuscated_frame = httpreq.obfuscate(&environ).http_frame().to_string();
-                    self.traffic_log
-                        .emit(TrafficLogMsg::SendingHttpRequest(obfuscated_frame));
-
-                    self.traffic_log
-                        .emit(TrafficLogMsg::RequestSent(httpreq.http_frame().len()));
-
-                    let req_response = req.send();
-                    if let Err(err) = req_response {
-                        self.response_body
-                            .emit(ResponseBodyMsg::ReceivingError(err.to_string()));
-                        self.traffic_log
-                            .emit(TrafficLogMsg::ReceivingError(err.to_string()));
-                        return;
+    fn update_cmd(
+        &mut self,
+        message: Self::CommandOutput,
+        _sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match message {
+            AppCmd::Running(run_id, _) | AppCmd::Done(run_id, _, _) if run_id != self.run_id => {
+                debug!("Ignoring event of the cancelled run {}", run_id);
+            }
+            AppCmd::Running(_, RunEvent::Sending(obfuscated_request, length)) => {
+                self.awaiting_response = true;
+                self.traffic_log
+                    .emit(TrafficLogMsg::SendingHttpRequest(obfuscated_request));
+                self.traffic_log.emit(TrafficLogMsg::RequestSent(length));
+            }
+            AppCmd::Running(_, RunEvent::Received(response)) => {
+                self.awaiting_response = false;
+                self.status_line.emit(StatusLineMsg::ReceivingHttpResponse(
+                    response.http_frame(),
+                    response.elapsed(),
+                    response.timing().map(|timing| timing.waterfall()),
+                ));
+                self.response_body
+                    .emit(ResponseBodyMsg::ReceivingHttpResponse(response.clone()));
+                if let Some((request_id, environment_id)) = self.run_target {
+                    self.workspace
+                        .add_response(request_id, HistoryEntry::new(&response, environment_id));
+                    if self.request_editor.model().request_id() == Some(request_id) {
+                        self.show_history(request_id);
                     }
-                    let response = req_response.unwrap();
-                    let mut resp = String::new();
-                    // response.read_to_string(&mut resp).unwrap();
-                    let version = format!("{:?}", response.version());
-                    resp.push_str(version.as_str());
-                    resp.push(' ');
-
-                    resp.push_str(response.status().as_str());
-                    resp.push(' ');
-                    resp.push_str(response.status().canonical_reason().unwrap_or(""));
-                    resp.push_str("\r\n");
-                    for (key, hval) in response.headers() {
-                        resp.push_str(key.to_string().as_str());
-                        resp.push_str(": ");
-                        resp.push_str(&String::from_utf8_lossy(hval.as_bytes()));
-                        resp.push_str("\r\n");
+                }
+                self.traffic_log
+                    .emit(TrafficLogMsg::ReceivingHttpResponse(response));
+                debug!("Done with the request");
+            }
+            AppCmd::Running(_, RunEvent::Asserted(assertions)) => {
+                self.status_line
+                    .emit(StatusLineMsg::ReceivingAssertions(assertions.clone()));
+                self.traffic_log.emit(TrafficLogMsg::Asserted(assertions));
+            }
+            AppCmd::Running(_, RunEvent::SendingError(error)) => {
+                self.awaiting_response = false;
+                self.traffic_log.emit(TrafficLogMsg::ReceivingError(error));
+            }
+            AppCmd::Done(_, error, position) => {
+                self.running = None;
+                self.awaiting_response = false;
+                self.status_line.emit(StatusLineMsg::HttpRequestDone);
+                let run_request_id = self.run_target.map(|(request_id, _)| request_id);
+                if let (Some(error), Some(position)) = (error.as_ref(), position) {
+                    // the template may have been switched during the run.
+                    if run_request_id.is_some()
+                        && self.request_editor.model().request_id() == run_request_id
+                    {
+                        self.request_editor
+                            .emit(RequestMsg::MarkError(position, error.clone()));
                     }
-                    resp.push_str("\r\n");
-                    resp.push_str(&response.text().unwrap());
-
-                    let duration = time.elapsed().unwrap(); // SystemTimeError!
-                    debug!("Response: {}", resp);
-                    self.status_line
-                        .emit(StatusLineMsg::ReceivingHttpResponse(resp.clone(), duration));
+                }
+                if let Some(error) = error {
                     self.response_body
-                        .emit(ResponseBodyMsg::ReceivingHttpResponse(resp.clone()));
-                    self.traffic_log
-                        .emit(TrafficLogMsg::ReceivingHttpResponse(resp));
-                    debug!("Done with the request");
+                        .emit(ResponseBodyMsg::ReceivingError(error));
                 }
                 debug!("Done with all the requests")
             }